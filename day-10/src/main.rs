@@ -1,4 +1,4 @@
-use std::{fs::{read_to_string, self}, collections::HashSet};
+use std::{env::args, fs::{read_to_string, self}, collections::HashSet, fmt::{self, Display, Formatter}};
 
 
 
@@ -30,6 +30,8 @@ impl Direction {
     }
 }
 
+const ALL_DIRECTIONS : [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+
 #[derive(Debug,Clone, Copy, PartialEq, Eq)]
 enum Pipe {
     Vertical,
@@ -69,6 +71,11 @@ impl Pipe {
         }
     }
 
+    fn from_ends(a : Direction, b : Direction) -> Option<Self> {
+        [Pipe::Vertical, Pipe::Horizontal, Pipe::NorthEast, Pipe::NorthWest, Pipe::SouthEast, Pipe::SouthWest].into_iter()
+            .find(|p| p.in_and_out().is_some_and(|ends| ends == (a, b) || ends == (b, a)))
+    }
+
     fn next(&self, dir : Direction) -> Option<Direction> {
         let (in_dir, out_dir) = self.in_and_out()?;
         (dir.opposite() == in_dir).then_some(out_dir).or((dir.opposite() == out_dir).then_some(in_dir))
@@ -86,13 +93,78 @@ impl Pipe {
             Pipe::Animal => 'S'
         }
     }
+
+    fn box_char(&self) -> char {
+        match self {
+            Pipe::Vertical => '│',
+            Pipe::Horizontal => '─',
+            Pipe::NorthEast => '└',
+            Pipe::NorthWest => '┘',
+            Pipe::SouthEast => '┌',
+            Pipe::SouthWest => '┐',
+            Pipe::Ground => ' ',
+            Pipe::Animal => 'S'
+        }
+    }
+}
+
+#[derive(Debug)]
+enum MazeError {
+    // line, column and character
+    UnknownTile(usize, usize, char),
+    NoAnimal,
+    SeveralAnimals,
+    // the directions from the animal with a pipe coming back to it
+    Disconnected(Vec<Direction>),
+    NoLoop(Vec<Direction>)
+}
+
+impl Display for MazeError {
+    fn fmt(&self, f : &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MazeError::UnknownTile(x, y, c) => write!(f, "unknown tile '{}' at line {}, column {}", c, x, y),
+            MazeError::NoAnimal => write!(f, "no S in the maze"),
+            MazeError::SeveralAnimals => write!(f, "more than one S in the maze"),
+            MazeError::Disconnected(dirs) => write!(f, "S needs two connected pipes, only found {:?}", dirs),
+            MazeError::NoLoop(dirs) => write!(f, "none of the pipes connected to S ({:?}) lead back to it", dirs)
+        }
+    }
 }
 
+// ANSI backgrounds : blue and bright black
+const INSIDE_BACKGROUND : &str = "\x1b[44m";
+const OUTSIDE_BACKGROUND : &str = "\x1b[100m";
+
+#[derive(Debug,Clone, Copy, PartialEq, Eq)]
+enum InsideMethod {
+    // flood fill of the tiles to the right of the loop
+    FloodRight,
+    // flood fill of the outside, on a map with room between every tile
+    Doubled,
+    // shoelace formula and Pick's theorem, in the loop length
+    Shoelace
+}
+
+impl InsideMethod {
+    fn parse(name : &str) -> Option<Self> {
+        match name {
+            "flood" => Some(Self::FloodRight),
+            "doubled" => Some(Self::Doubled),
+            "shoelace" => Some(Self::Shoelace),
+            _ => None
+        }
+    }
+}
+
+// the tiles of a walk back to its start and the direction of its last step, or of a chain open at one end
+type Walk = Result<(Vec<(usize, usize)>, Direction), Vec<(usize, usize)>>;
+
 struct UnLooped {
     animal : (usize, usize)
 }
 
 struct Looped {
+    path : Vec<(usize, usize)>,
     loop_set : HashSet<(usize, usize)>
 }
 
@@ -117,93 +189,98 @@ impl<T> HotSpring<T> {
         Some((self.at(next_pos)?,next_pos))
     }
 
+    fn render_table(&self) -> String {
+        self.table.iter().map(|l| l.iter().map(Pipe::char).collect::<String>() + "\n").collect()
+    }
+
+    // the tiles met going `dir` from `start` until coming back, and the direction of the last step,
+    // or, if the pipes lead nowhere, the tiles met before : a chain that is open at one end
+    fn walk(&self, start : (usize, usize), dir : Direction) -> Walk {
+        let (mut path, mut pos, mut dir) = (vec![start], start, dir);
+        loop {
+            let Some((tile, next_pos)) = self.next(pos, dir) else {return Err(path)};
+            if next_pos == start {
+                return Ok((path, dir));
+            }
+            // a tile not connecting back is not part of the chain, and may still be on a loop
+            let Some(next_dir) = tile.next(dir) else {return Err(path)};
+            path.push(next_pos);
+            (pos, dir) = (next_pos, next_dir);
+        }
+    }
+
+    // every loop made of pipes only, each from its top left tile
+    fn all_loops(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = self.table.iter().map(|l| vec![false; l.len()]).collect::<Vec<_>>();
+        let mut loops = vec![];
+        for (x, line) in self.table.iter().enumerate() {
+            for (y, pipe) in line.iter().enumerate() {
+                let Some((out_dir, in_dir)) = pipe.in_and_out().filter(|_| !seen[x][y]) else {continue};
+                // coming back from the wrong side leaves the last tile of the walk without its second end
+                match self.walk((x, y), out_dir) {
+                    Ok((path, arrival)) if arrival.opposite() == in_dir => {
+                        path.iter().for_each(|&(x, y)| seen[x][y] = true);
+                        loops.push(path);
+                    },
+                    Ok((path, _)) | Err(path) => path.iter().for_each(|&(x, y)| seen[x][y] = true)
+                };
+            }
+        };
+        loops
+    }
+
+}
+
+impl HotSpring<()> {
+    fn parse<'a, I>(lines : I) -> Result<Self, MazeError>
+    where
+        I : Iterator<Item = &'a str>
+    {
+        let table = lines.enumerate().map(|(x, s)| s.chars().enumerate()
+            .map(|(y, c)| Pipe::parse(c).ok_or(MazeError::UnknownTile(x + 1, y + 1, c))).collect::<Result<Vec<_>, _>>()
+        ).collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { table, maybe_loop : () })
+    }
 }
 
 impl HotSpring<UnLooped> {
-    fn new<'a, I>(lines : I) -> Option<Self>
+    fn new<'a, I>(lines : I) -> Result<Self, MazeError>
     where
         I : Iterator<Item = &'a str>
     {
-        let table = lines.map(|s| s.chars().map(Pipe::parse).collect::<Option<Vec<_>>>()).collect::<Option<Vec<_>>>()?;
-        let animal = table.iter().enumerate().filter_map(|(x, l)| l.iter().enumerate().filter_map(|(y, p)| (*p == Pipe::Animal).then_some((x, y))).next()).next()?;
-        Some( Self { 
+        let table = HotSpring::parse(lines)?.table;
+        let mut animals = table.iter().enumerate().flat_map(|(x, l)| l.iter().enumerate().filter_map(move |(y, p)| (*p == Pipe::Animal).then_some((x, y))));
+        let animal = animals.next().ok_or(MazeError::NoAnimal)?;
+        if animals.next().is_some() {
+            return Err(MazeError::SeveralAnimals);
+        }
+        Ok( Self { 
             table,
             maybe_loop : UnLooped { animal }
         })
     }
 
-    fn classic_length(&self) -> Option<usize> {
-        vec![
-            Direction::North,
-            Direction::South,
-            Direction::East,
-        ].into_iter().filter_map(|start_dir| {
-            let (mut steps, mut pos, mut dir, mut tile) : (usize, _, _, _) = 
-            (0, self.maybe_loop.animal, start_dir, Pipe::Animal);
-            loop {
-                (tile, pos) = self.next(pos, dir)?;
-                steps += 1;
-                if tile == Pipe::Animal {
-                    return Some(steps / 2)
-                }
-                dir = tile.next(dir)?
-            }
-
-        }).next()
-    }
-
-    fn looped(mut self) -> Result<HotSpring<Looped>, HotSpring<UnLooped>> {
-        match self.complete_loop() {
-            Some(loop_set) => Ok(HotSpring { table: self.table , maybe_loop: Looped { loop_set } }),
-            None => Err(self)
-        }
+    fn looped(mut self) -> Result<HotSpring<Looped>, MazeError> {
+        let path = self.complete_loop()?;
+        let loop_set = path.iter().copied().collect();
+        Ok(HotSpring { table: self.table , maybe_loop: Looped { path, loop_set } })
     }
 
-    fn complete_loop(&mut self) -> Option<HashSet<(usize, usize)>> {
-        let dirs = vec![
-            Direction::North,
-            Direction::South,
-            Direction::East,
-            Direction::West,
-        ].into_iter().filter_map(|start_dir| {
-            let mut res = HashSet::<(usize, usize)>::new();
-            res.insert(self.maybe_loop.animal);
-            let mut dir = start_dir;
-            let (mut tile, mut pos) = self.next(self.maybe_loop.animal, dir)?;
-            loop {
-                if !res.insert(pos) {
-                    return Some(start_dir)
-                }
-                dir = tile.next(dir)?;
-                (tile, pos) = self.next(pos, dir)?;
-            }
-        }).collect::<Vec<_>>();
-        let real_tile = match (dirs.get(0)?, dirs.get(1)?) {
-            (Direction::North, Direction::South) => Pipe::Vertical,
-            (Direction::North, Direction::East) => Pipe::NorthEast,
-            (Direction::North, Direction::West) => Pipe::NorthWest,
-            (Direction::South, Direction::East) => Pipe::SouthEast,
-            (Direction::South, Direction::West) => Pipe::SouthWest,
-            (Direction::East, Direction::West) => Pipe::Horizontal,
-            _ => unreachable!("no other pair should be reachable from the start")
-        };
-        let (x,y) = self.maybe_loop.animal;
-        self.table[x][y] = real_tile;
-        println!("new animal : {:?}", self.at(self.maybe_loop.animal));
-        let mut res = HashSet::<(usize, usize)>::new();
-        res.insert(self.maybe_loop.animal);
-        let mut dir = *dirs.get(0).expect("this was already red before");
-        let (mut tile, mut pos) = self.next(self.maybe_loop.animal, dir)?;
-        loop {
-            if !res.insert(pos) {
-                return Some(res)
-            }
-            dir = tile.next(dir)?;
-            (tile, pos) = self.next(pos, dir)?;
+    // the loop, in walking order from the animal, whose tile becomes the pipe the loop goes through
+    fn complete_loop(&mut self) -> Result<Vec<(usize, usize)>, MazeError> {
+        let animal = self.maybe_loop.animal;
+        let connected = ALL_DIRECTIONS.into_iter()
+            .filter(|&d| self.next(animal, d).and_then(|(tile, _)| tile.next(d)).is_some()).collect::<Vec<_>>();
+        if connected.len() < 2 {
+            return Err(MazeError::Disconnected(connected));
         }
+        let (path, start_dir, end_dir) = connected.iter()
+            .find_map(|&d| self.walk(animal, d).ok().map(|(path, arrival)| (path, d, arrival.opposite())))
+            .ok_or_else(|| MazeError::NoLoop(connected.clone()))?;
+        let (x,y) = animal;
+        self.table[x][y] = Pipe::from_ends(start_dir, end_dir).ok_or(MazeError::NoLoop(connected))?;
+        Ok(path)
     }
-
-//    fn insides_set_alt
 }
 
 impl HotSpring<Looped> {
@@ -225,8 +302,10 @@ impl HotSpring<Looped> {
         let mut insides_set = HashSet::<(usize, usize)>::new();
         let (mut pos, mut dir, mut tile)= 
             (*left_top_most, Direction::East, self.at(*left_top_most).expect("left_top_most is from the loop"));
+        let mut incoming = dir;
         loop {
-            if let Some((_, right_pos)) = self.next(pos, dir.right()) {
+            // on a corner, the tile to the right of the way in is also inside
+            for right_pos in [dir, incoming].iter().filter_map(|d| self.next(pos, d.right())).map(|(_, p)| p) {
                 if !loop_set.contains(&right_pos) {
                     let mut todo_set = vec![right_pos];
                     while let Some(next_pos) = todo_set.pop() {
@@ -246,18 +325,34 @@ impl HotSpring<Looped> {
                 }
             }
             (tile, pos) = self.next(pos, dir).expect(" pos is in the loop it should be fine to find next pos");
+            incoming = dir;
             if pos == *left_top_most {
-                println!("intersection : {:?}", insides_set.intersection(&loop_set));
                 return insides_set
             }
             dir = tile.next(dir).expect(&format!("next pos ({:?}, {:?}) is in the loop it should be fine to find next dir ({:?})", tile, pos, dir))
         }
     }
 
-    fn alternate_insides_set(&self) -> HashSet<(usize, usize)> {
+    fn shoelace_insides(&self) -> usize {
+        shoelace_insides(&self.maybe_loop.path)
+    }
+
+    fn insides_count(&self, method : InsideMethod) -> usize {
+        match method {
+            InsideMethod::FloodRight => self.insides_set().len(),
+            InsideMethod::Doubled => self.alternate_insides_set().len(),
+            InsideMethod::Shoelace => self.shoelace_insides()
+        }
+    }
+
+    // the map with room between every tile, where the loop is the only pipe left
+    fn doubled(&self) -> HotSpring<Looped> {
         let mut biggenedHS = HotSpring {
             table: deep_alternate_with(&self.cleaned(), Pipe::Ground),
-            maybe_loop : Looped { loop_set: self.maybe_loop.loop_set.iter().map(|(x,y)| (2*x,2*y)).collect()}
+            maybe_loop : Looped {
+                path: self.maybe_loop.path.iter().map(|(x,y)| (2*x,2*y)).collect(),
+                loop_set: self.maybe_loop.loop_set.iter().map(|(x,y)| (2*x,2*y)).collect()
+            }
         };
         self.maybe_loop.loop_set.iter().for_each(|(x,y)| {
             let (a,b) = self.at((*x,*y)).expect("the elements of the loop are inside the map").in_and_out().expect("the elements of the loop have ins and outs");
@@ -267,34 +362,82 @@ impl HotSpring<Looped> {
             })
             }
         );
-        fs::write("output2.txt", biggenedHS.table.iter().enumerate().map(|(x, l)| l.iter().enumerate().map(|(y, p)| {
-            p.char()
-        }).collect::<String>() + "\n").collect::<String>()).unwrap();
-        let mut outside_set = HashSet::new();
-        outside_set.insert((0,0));
-        let mut todo_vec = vec![(0,0)];
-        while let Some(next_pos) = todo_vec.pop() {
-            let next_pos_list = vec![
-                Direction::North,
-                Direction::South,
-                Direction::East,
-                Direction::West,
-            ].iter().filter_map(|d| {
-                biggenedHS.next(next_pos, *d).filter(|(pipe,p)| *pipe == Pipe::Ground && !outside_set.contains(p))
-            }).collect::<Vec<_>>();
-            next_pos_list.iter().for_each(|(_,p)| {
-                outside_set.insert(*p);
-                todo_vec.push(*p)
-            }
-            );
+        biggenedHS
+    }
+
+    fn alternate_insides_set(&self) -> HashSet<(usize, usize)> {
+        let biggened = self.doubled().table;
+        // a ground border around the doubled map, so the outside can go around the loop where it touches the edge
+        let (height, width) = (biggened.len() + 2, biggened.first().map_or(0, Vec::len) + 2);
+        let is_ground = |(x, y) : (usize, usize)| x == 0 || y == 0 || x + 1 == height || y + 1 == width || biggened[x - 1][y - 1] == Pipe::Ground;
+        let mut outside_set = HashSet::from([(0, 0)]);
+        let mut todo_vec = vec![(0_usize, 0_usize)];
+        while let Some((x, y)) = todo_vec.pop() {
+            [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)].into_iter()
+                .filter(|&(x, y)| x < height && y < width && is_ground((x, y)))
+                .for_each(|p| if outside_set.insert(p) {
+                    todo_vec.push(p)
+                });
         }
-        let smaller_outside = outside_set.iter().filter_map(|(x,y)| (x % 2 == 0 && y % 2 == 0).then_some((x / 2, y / 2))).collect::<HashSet<_>>();
-        let all = self.table.iter().enumerate().map(|(x, line)| {
+        let smaller_outside = outside_set.iter()
+            .filter(|(x,y)| x % 2 == 1 && y % 2 == 1).map(|(x,y)| ((x - 1) / 2, (y - 1) / 2)).collect::<HashSet<_>>();
+        let all = self.table.iter().enumerate().flat_map(|(x, line)| {
             (0..line.len()).map(move |y| (x,y))
-        }).flatten().collect::<HashSet<_>>();
+        }).collect::<HashSet<_>>();
 
-        all.difference(&smaller_outside).map(|p|*p).collect::<HashSet<_>>().difference(&self.maybe_loop.loop_set).map(|p|*p).collect::<HashSet<_>>()
+        all.difference(&smaller_outside).copied().collect::<HashSet<_>>().difference(&self.maybe_loop.loop_set).copied().collect::<HashSet<_>>()
     }
+
+    // the loop as is, I for the tiles inside and O for the rest
+    fn render(&self) -> String {
+        let inside_set = self.insides_set();
+        self.table.iter().enumerate().map(|(x, l)| l.iter().enumerate().map(|(y, p)| {
+            if self.maybe_loop.loop_set.contains(&(x,y)) {
+                p.char()
+            } else if inside_set.contains(&(x,y)) {
+                'I'
+            } else {
+                'O'
+            }
+        }).collect::<String>() + "\n").collect()
+    }
+
+    // box-drawing pipes for terminals : pipes off the loop are dimmed,
+    // tiles inside have a blue background and tiles outside a grey one
+    fn render_unicode(&self) -> String {
+        let inside_set = self.insides_set();
+        self.table.iter().enumerate().map(|(x, l)| l.iter().enumerate().map(|(y, p)| {
+            let on_loop = self.maybe_loop.loop_set.contains(&(x,y));
+            let background = match (on_loop, inside_set.contains(&(x,y))) {
+                (true, _) => "",
+                (false, true) => INSIDE_BACKGROUND,
+                (false, false) => OUTSIDE_BACKGROUND
+            };
+            let style = if on_loop {"\x1b[1m"} else {"\x1b[2m"};
+            format!("{}{}{}\x1b[0m", background, style, p.box_char())
+        }).collect::<String>() + "\n").collect()
+    }
+
+    // the loop as a filled polygon through the centers of its tiles
+    fn render_svg(&self) -> String {
+        const TILE : usize = 10;
+        let (height, width) = (self.table.len() * TILE, self.table.first().map_or(0, Vec::len) * TILE);
+        let points = self.maybe_loop.path.iter().map(|(x, y)| format!("{},{}", y * TILE + TILE / 2, x * TILE + TILE / 2))
+            .collect::<Vec<_>>().join(" ");
+        format!(concat!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            "  <rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n",
+            "  <polygon points=\"{points}\" fill=\"lightblue\" stroke=\"navy\" stroke-width=\"2\" stroke-linejoin=\"round\"/>\n",
+            "</svg>\n"
+        ), w = width, h = height, points = points)
+    }
+}
+
+// Pick's theorem : area = insides + boundary / 2 - 1, with the area from the shoelace formula
+fn shoelace_insides(path : &[(usize, usize)]) -> usize {
+    let double_area = path.iter().zip(path.iter().cycle().skip(1))
+        .map(|(&(x1, y1), &(x2, y2))| (x1 * y2) as i64 - (x2 * y1) as i64).sum::<i64>().unsigned_abs() as usize;
+    (double_area + 2).saturating_sub(path.len()) / 2
 }
 
 fn alternate_with(vec : &Vec<Pipe>, p : Pipe) -> Vec<Pipe> {
@@ -328,25 +471,190 @@ fn deep_alternate_with(vec : &Vec<Vec<Pipe>>, p : Pipe) -> Vec<Vec<Pipe>> {
 }
 
 
+// every loop of the maze at `path`, which may have no animal
+fn print_loops(path : &str) -> Result<(), ()> {
+    let input = read_to_string(path).map_err(|e| eprintln!("could not read {} : {}", path, e))?;
+    let maze = HotSpring::parse(input.lines()).map_err(|e| eprintln!("{} : {}", path, e))?;
+    let loops = maze.all_loops();
+    println!("{} loops in {}", loops.len(), path);
+    loops.iter().for_each(|l|
+        println!("loop from {:?} : length {}, farthest {}, enclosing {}", l[0], l.len(), l.len() / 2, shoelace_insides(l))
+    );
+    Ok(())
+}
+
+// usage : day-10 [--method flood|doubled|shoelace] [--render PATH] [--render-unicode PATH] [--render-svg PATH] [--render-doubled PATH] [--loops PATH]
 fn main() -> Result<(),()>{
-    let hot_spring = HotSpring::new(read_to_string("input.txt").unwrap_or("".to_string()).lines()).ok_or(())?.looped().map_err(|_|())?;
+    if let Some(path) = args().skip_while(|arg| arg != "--loops").nth(1) {
+        return print_loops(&path);
+    }
+    let hot_spring = HotSpring::new(read_to_string("input.txt").unwrap_or("".to_string()).lines())
+        .and_then(HotSpring::looped).map_err(|e| eprintln!("{}", e))?;
     println!("part 1 : {:?}", hot_spring.classic_length());
-    let loop_set = &hot_spring.maybe_loop.loop_set;
-    let inside_set = hot_spring.insides_set();
-    println!("part 2 : {:?}", inside_set.len());
-    println!("part 2 bis : {:?}", hot_spring.alternate_insides_set().len());
-    hot_spring.alternate_insides_set().symmetric_difference(&inside_set).for_each(|p|
-        println!("difference : {:?}", p)
-    );
-    fs::write("output.txt", hot_spring.table.iter().enumerate().map(|(x, l)| l.iter().enumerate().map(|(y, p)| {
-        if loop_set.contains(&(x,y)) {
-            p.char()
-        } else if inside_set.contains(&(x,y)) {
-            'I'
-        } else {
-            'O'
-        }
-    }).collect::<String>() + "\n").collect::<String>()).unwrap();
-    hot_spring.alternate_insides_set();
+    let method = match args().skip_while(|arg| arg != "--method").nth(1) {
+        Some(name) => InsideMethod::parse(&name).ok_or_else(|| eprintln!("unknown method {}, expected flood, doubled or shoelace", name))?,
+        None => InsideMethod::Shoelace
+    };
+    println!("part 2 : {:?}", hot_spring.insides_count(method));
+    if let Some(path) = args().skip_while(|arg| arg != "--render").nth(1) {
+        fs::write(&path, hot_spring.render()).map_err(|e| eprintln!("could not write {} : {}", path, e))?;
+    }
+    if let Some(path) = args().skip_while(|arg| arg != "--render-unicode").nth(1) {
+        fs::write(&path, hot_spring.render_unicode()).map_err(|e| eprintln!("could not write {} : {}", path, e))?;
+    }
+    if let Some(path) = args().skip_while(|arg| arg != "--render-svg").nth(1) {
+        fs::write(&path, hot_spring.render_svg()).map_err(|e| eprintln!("could not write {} : {}", path, e))?;
+    }
+    if let Some(path) = args().skip_while(|arg| arg != "--render-doubled").nth(1) {
+        fs::write(&path, hot_spring.doubled().render_table()).map_err(|e| eprintln!("could not write {} : {}", path, e))?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METHODS : [InsideMethod; 3] = [InsideMethod::FloodRight, InsideMethod::Doubled, InsideMethod::Shoelace];
+
+    fn looped(maze : &str) -> HotSpring<Looped> {
+        HotSpring::new(maze.lines()).and_then(HotSpring::looped).unwrap()
+    }
+
+    fn check(maze : &str, length : usize, insides : usize) {
+        let hot_spring = looped(maze);
+        assert_eq!(hot_spring.classic_length(), length);
+        for method in METHODS {
+            assert_eq!(hot_spring.insides_count(method), insides, "{:?}", method);
+        }
+        assert_eq!(hot_spring.insides_set(), hot_spring.alternate_insides_set());
+    }
+
+    #[test]
+    fn maze_errors() {
+        let error = |maze : &str| HotSpring::new(maze.lines()).and_then(HotSpring::looped).err().unwrap();
+        assert!(matches!(error(".S.7."), MazeError::Disconnected(dirs) if dirs.is_empty()));
+        assert!(matches!(error(".S-.\n...."), MazeError::Disconnected(dirs) if dirs == vec![Direction::East]));
+        assert!(matches!(error(".....\n.S-7.\n.|.|.\n.L-|.\n"), MazeError::NoLoop(dirs) if dirs == vec![Direction::South, Direction::East]));
+        assert!(matches!(error("S-S"), MazeError::SeveralAnimals));
+        assert!(matches!(error("...\n..."), MazeError::NoAnimal));
+        assert!(matches!(error("F-7\n|x|"), MazeError::UnknownTile(2, 2, 'x')));
+    }
+
+    // the start tile becomes the pipe closing the loop
+    #[test]
+    fn start_tile_inferred() {
+        let hot_spring = looped("..F7.\n.FJ|.\nSJ.L7\n|F--J\nLJ...");
+        assert_eq!(hot_spring.at((2, 0)), Some(Pipe::SouthEast));
+    }
+
+    fn loops(maze : &str) -> Vec<(usize, usize)> {
+        HotSpring::parse(maze.lines()).unwrap().all_loops().iter().map(|l| (l.len(), shoelace_insides(l))).collect()
+    }
+
+    #[test]
+    fn every_loop() {
+        assert_eq!(loops(".....\n.F-7.\n.|.|.\n.L-J.\n.....\nF7...\nLJ.F7\n...||\n..FJ|\n..L-J\n"), vec![(8, 1), (4, 0), (10, 0)]);
+        assert_eq!(loops("F-7\n|.|\nL-."), vec![]);
+        assert_eq!(loops(&format!("F{}", "-".repeat(1000))), vec![]);
+        // the open chain on the left ends against the loop, which must still be found
+        assert_eq!(loops("-F7\n.LJ"), vec![(4, 0)]);
+        assert_eq!(loops("F-7F7\n|.LJ|\nL---J"), vec![(14, 1)]);
+    }
+
+    #[test]
+    fn unicode_shades_inside_and_outside() {
+        let rendered = looped(".....\n.S-7.\n.|.|.\n.L-J.\n.....").render_unicode();
+        assert_eq!(rendered.matches(INSIDE_BACKGROUND).count(), 1);
+        assert_eq!(rendered.matches(OUTSIDE_BACKGROUND).count(), 16);
+        assert_eq!(rendered.lines().nth(1).unwrap().matches('─').count(), 1);
+    }
+
+    #[test]
+    fn square() {
+        check(".....\n.S-7.\n.|.|.\n.L-J.\n.....", 4, 1);
+    }
+
+    // the loop touches the edges of the map
+    #[test]
+    fn on_the_edges() {
+        check("..F7.\n.FJ|.\nSJ.L7\n|F--J\nLJ...", 8, 1);
+        check("S7\nLJ", 2, 0);
+    }
+
+    #[test]
+    fn enclosed() {
+        check(concat!(
+            "...........\n",
+            ".S-------7.\n",
+            ".|F-----7|.\n",
+            ".||.....||.\n",
+            ".||.....||.\n",
+            ".|L-7.F-J|.\n",
+            ".|..|.|..|.\n",
+            ".L--J.L--J.\n",
+            "..........."
+        ), 23, 4);
+    }
+
+    // the outside squeezes between pipes
+    #[test]
+    fn squeezed() {
+        check(concat!(
+            "..........\n",
+            ".S------7.\n",
+            ".|F----7|.\n",
+            ".||....||.\n",
+            ".||....||.\n",
+            ".|L-7F-J|.\n",
+            ".|..||..|.\n",
+            ".L--JL--J.\n",
+            ".........."
+        ), 22, 4);
+    }
+
+    #[test]
+    fn larger() {
+        check(concat!(
+            ".F----7F7F7F7F-7....\n",
+            ".|F--7||||||||FJ....\n",
+            ".||.FJ||||||||L7....\n",
+            "FJL7L7LJLJ||LJ.L-7..\n",
+            "L--J.L7...LJS7F-7L7.\n",
+            "....F-J..F7FJ|L7L7L7\n",
+            "....L7.F7||L7|.L7L7|\n",
+            ".....|FJLJ|FJ|F7|.LJ\n",
+            "....FJL-7.||.||||...\n",
+            "....L---J.LJ.LJLJ..."
+        ), 70, 8);
+    }
+
+    // junk pipes around the loop, which also runs along every edge
+    #[test]
+    fn junk() {
+        check(concat!(
+            "FF7FSF7F7F7F7F7F---7\n",
+            "L|LJ||||||||||||F--J\n",
+            "FL-7LJLJ||||||LJL-77\n",
+            "F--JF--7||LJLJ7F7FJ-\n",
+            "L---JF-JLJ.||-FJLJJ7\n",
+            "|F|F-JF---7F7-L7L|7|\n",
+            "|FFJF7L7F-JF7|JL---7\n",
+            "7-L-JL7||F7|L7F-7F7|\n",
+            "L.L7LFJ|||||FJL7||LJ\n",
+            "L7JLJL-JLJLJL--JLJ.L"
+        ), 80, 10);
+    }
+
+    // one polygon point per loop tile, at the centre of its tile, in a picture the size of the map
+    #[test]
+    fn svg_export() {
+        let hot_spring = looped(".....\n.S-7.\n.|.|.\n.L-J.\n.....");
+        let svg = hot_spring.render_svg();
+        assert!(svg.contains("width=\"50\" height=\"50\" viewBox=\"0 0 50 50\""));
+        let points = svg.split("points=\"").nth(1).and_then(|rest| rest.split('"').next()).unwrap();
+        assert_eq!(points.split(' ').count(), hot_spring.maybe_loop.path.len());
+        assert!(points.split(' ').all(|point| point.split(',').all(|c| c.parse::<usize>().is_ok_and(|c| c % 10 == 5 && c < 50))));
+        assert!(points.split(' ').any(|point| point == "15,15"));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, fs::read_to_string};
+use std::{env::args, fmt::{self, Display, Formatter}, fs::read_to_string};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -8,15 +8,40 @@ enum Tile {
 }
 
 impl Tile {
-    fn parse(c : char) -> Self {
+    fn parse(c : char) -> Result<Self, GalaxyError> {
         match c {
-            '.' => Tile::Void,
-            '#' => Tile::Star,
-            _ => panic!("char '{}' is not allowed", c)
+            '.' => Ok(Tile::Void),
+            '#' => Ok(Tile::Star),
+            _ => Err(GalaxyError::UnknownTile(c))
         }
     }
 }
 
+#[derive(Debug)]
+enum GalaxyError {
+    UnknownTile(char),
+    // stars are numbered from 1, in reading order
+    NoSuchStar(usize),
+    // line number and what went wrong on it
+    OnLine(usize, Box<GalaxyError>)
+}
+
+impl Display for GalaxyError {
+    fn fmt(&self, f : &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GalaxyError::UnknownTile(c) => write!(f, "char '{}' is not allowed", c),
+            GalaxyError::NoSuchStar(n) => write!(f, "there is no star number {}", n),
+            GalaxyError::OnLine(line, e) => write!(f, "line {} : {}", line, e)
+        }
+    }
+}
+
+fn parse_table(input : &str) -> Result<Vec<Vec<Tile>>, GalaxyError> {
+    input.lines().enumerate().map(|(x, l)|
+        l.chars().map(Tile::parse).collect::<Result<Vec<_>, _>>().map_err(|e| GalaxyError::OnLine(x + 1, Box::new(e)))
+    ).collect()
+}
+
 struct Galaxy {
     columns : Vec<usize>,
     lines : Vec<usize>,
@@ -25,7 +50,8 @@ struct Galaxy {
 
 impl Galaxy {
 
-    fn new<I,J>(table : I, time : usize) -> Self
+    // empty lines count as `line_time` lines, and empty columns as `column_time` columns
+    fn new<I,J>(table : I, line_time : usize, column_time : usize) -> Self
     where
         I : Iterator<Item = J>,
         J : Iterator<Item = Tile>
@@ -34,10 +60,10 @@ impl Galaxy {
             (vec![], vec![], vec![]);
 
         table.enumerate().for_each(|(x,l)|{
-            lines.push(time);
+            lines.push(line_time);
             l.enumerate().for_each(|(y,t)|{
                 if y >= columns.len() {
-                    columns.push(time)
+                    columns.push(column_time)
                 }
                 if t == Tile::Star {
                     lines[x] = 1;
@@ -65,21 +91,61 @@ impl Galaxy {
         (self.lines[x2] - self.lines[x1]) + (self.columns[y2] - self.columns[y1])
     }
 
-    fn distances(&self) -> Vec<((usize,usize), (usize, usize), usize)> {
-        let mut res = vec![];
-        res.reserve_exact((self.stars.len() * (self.stars.len() + 1)) / 2);
-        for (i,s1) in self.stars[0..self.stars.len()].iter().enumerate() {
-            for s2 in self.stars[(i + 1)..self.stars.len()].iter() {
-                res.push((
-                    *s1,
-                    *s2,
-                    self.distance(*s1, *s2)
-                ))
-            }
-        };
-        res
-    }
-    
+    // where the stars end up once the galaxy has expanded : a star's line is counted in `lines`, hence the - 1
+    fn expanded(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.stars.iter().map(|(x, y)| (self.lines[*x] - 1, self.columns[*y] - 1))
+    }
+
+    fn star(&self, number : usize) -> Result<(usize, usize), GalaxyError> {
+        number.checked_sub(1).and_then(|i| self.stars.get(i)).copied().ok_or(GalaxyError::NoSuchStar(number))
+    }
+
+    fn star_distance(&self, a : usize, b : usize) -> Result<usize, GalaxyError> {
+        Ok(self.distance(self.star(a)?, self.star(b)?))
+    }
+
+    // for each star, the number of its closest other star and their distance, None if it is alone
+    fn nearest_neighbours(&self) -> Vec<Option<(usize, usize)>> {
+        self.stars.iter().enumerate().map(|(i, s1)|
+            self.stars.iter().enumerate().filter(|(j, _)| *j != i)
+                .map(|(j, s2)| (j + 1, self.distance(*s1, *s2))).min_by_key(|(_, d)| *d)
+        ).collect()
+    }
+
+    // the numbers of the two farthest stars and their distance :
+    // |dx| + |dy| is the largest of |(x + y) - (x' + y')| and |(x - y) - (x' - y')|
+    fn diameter(&self) -> Option<(usize, usize, usize)> {
+        let expanded = self.expanded().map(|(x, y)| (x as i64, y as i64)).collect::<Vec<_>>();
+        [|(x, y) : (i64, i64)| x + y, |(x, y) : (i64, i64)| x - y].into_iter().filter_map(|rotate| {
+            let min = (0..expanded.len()).min_by_key(|&i| rotate(expanded[i]))?;
+            let max = (0..expanded.len()).max_by_key(|&i| rotate(expanded[i]))?;
+            Some((min + 1, max + 1, (rotate(expanded[max]) - rotate(expanded[min])) as usize))
+        }).max_by_key(|(_, _, d)| *d)
+    }
+
+    // every pair of stars with its distance, lazily : there are n(n-1)/2 of them, so only the tests check against it
+    #[cfg(test)]
+    fn distances(&self) -> impl Iterator<Item = ((usize,usize), (usize, usize), usize)> + '_ {
+        self.stars.iter().enumerate().flat_map(move |(i, s1)|
+            self.stars[(i + 1)..].iter().map(move |s2| (*s1, *s2, self.distance(*s1, *s2)))
+        )
+    }
+
+    // sum of the distances over every pair of stars, one axis at a time
+    fn total_distance(&self) -> usize {
+        axis_total(self.stars.iter().map(|(x, _)| self.lines[*x]).collect())
+            + axis_total(self.stars.iter().map(|(_, y)| self.columns[*y]).collect())
+    }
+}
+
+// sum of |a - b| over every pair : once sorted, the i-th coordinate c adds c * i minus the sum of the ones before it
+fn axis_total(mut coordinates : Vec<usize>) -> usize {
+    coordinates.sort_unstable();
+    coordinates.iter().enumerate().scan(0, |prefix, (i, c)| {
+        let total = c * i - *prefix;
+        *prefix += c;
+        Some(total)
+    }).sum()
 }
 
 trait Order {
@@ -97,11 +163,85 @@ impl<T : Ord> Order for (T,T) {
 }
 
 
-fn main() {
+// the two numbers following `flag`, None without the flag
+fn pair_after(args : &[String], flag : &str) -> Result<Option<(usize, usize)>, String> {
+    let Some(position) = args.iter().position(|arg| arg == flag) else {return Ok(None)};
+    match args[position + 1..].iter().take(2).map(|s| s.parse::<usize>().ok()).collect::<Vec<_>>()[..] {
+        [Some(a), Some(b)] => Ok(Some((a, b))),
+        _ => Err(format!("{} expects two numbers", flag))
+    }
+}
+
+// usage : day-11 [--factors LINES COLUMNS] [--distance A B] [--nearest] [--diameter] [--coordinates]
+// the queries use the expansion factors given, or the ones of part 2
+fn main() -> Result<(), ()> {
+    let args = args().collect::<Vec<_>>();
+    let factors = pair_after(&args, "--factors").map_err(|e| eprintln!("{}", e))?;
+    let distance = pair_after(&args, "--distance").map_err(|e| eprintln!("{}", e))?;
     let input = read_to_string("input.txt").unwrap_or("".to_string());
-    let g = Galaxy::new(input.lines().map(|s|s.chars().map(Tile::parse)),2);
-    println!("part 1 : {}", Galaxy::new(input.lines().map(|s|s.chars().map(Tile::parse)),2)
-                                .distances().iter().map(|(_,_,l)|*l).sum::<usize>());
-    println!("part 2 : {}", Galaxy::new(input.lines().map(|s|s.chars().map(Tile::parse)),1000000)
-                                .distances().iter().map(|(_,_,l)|*l).sum::<usize>());
+    let table = parse_table(&input).map_err(|e| eprintln!("{}", e))?;
+    let galaxy = |line_time, column_time| Galaxy::new(table.iter().map(|l| l.iter().copied()), line_time, column_time);
+    let g = galaxy(2, 2);
+    println!("part 1 : {}", g.total_distance());
+    println!("part 2 : {}", galaxy(1000000, 1000000).total_distance());
+
+    let g = match factors {
+        Some((line_time, column_time)) => galaxy(line_time, column_time),
+        None => galaxy(1000000, 1000000)
+    };
+    if factors.is_some() {
+        println!("total distance : {}", g.total_distance());
+    }
+    if let Some((a, b)) = distance {
+        println!("distance between {} and {} : {}", a, b, g.star_distance(a, b).map_err(|e| eprintln!("{}", e))?);
+    }
+    if args.iter().any(|arg| arg == "--nearest") {
+        g.nearest_neighbours().iter().enumerate().for_each(|(i, n)| match n {
+            Some((j, d)) => println!("nearest to {} : {} at {}", i + 1, j, d),
+            None => println!("nearest to {} : none", i + 1)
+        });
+    }
+    if args.iter().any(|arg| arg == "--diameter") {
+        match g.diameter() {
+            Some((a, b, d)) => println!("diameter : {} between {} and {}", d, a, b),
+            None => println!("diameter : no stars")
+        }
+    }
+    if args.iter().any(|arg| arg == "--coordinates") {
+        g.expanded().enumerate().for_each(|(i, (x, y))| println!("{} : {:?}", i + 1, (x, y)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s : &str) -> Vec<String> {
+        s.split_ascii_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn pairs_of_numbers() {
+        assert_eq!(pair_after(&args("day-11 --factors 2 3"), "--factors"), Ok(Some((2, 3))));
+        assert_eq!(pair_after(&args("day-11 --diameter"), "--factors"), Ok(None));
+        assert!(pair_after(&args("day-11 --factors 5"), "--factors").is_err());
+        assert!(pair_after(&args("day-11 --distance 3 --nearest"), "--distance").is_err());
+    }
+
+    #[test]
+    fn example() {
+        let table = parse_table("...#......\n.......#..\n#.........\n..........\n......#...\n.#........\n.........#\n..........\n.......#..\n#...#.....\n").unwrap();
+        let galaxy = |line_time, column_time| Galaxy::new(table.iter().map(|l| l.iter().copied()), line_time, column_time);
+        let g = galaxy(2, 2);
+        assert_eq!(g.total_distance(), 374);
+        assert_eq!(g.total_distance(), g.distances().map(|(_, _, l)| l).sum::<usize>());
+        assert_eq!(galaxy(10, 10).total_distance(), 1030);
+        assert_eq!(galaxy(100, 100).total_distance(), 8410);
+        assert_eq!(g.star_distance(5, 9).unwrap(), 9);
+        assert!(matches!(g.star_distance(0, 10), Err(GalaxyError::NoSuchStar(0))));
+        assert_eq!(g.diameter().map(|(_, _, d)| d), g.distances().map(|(_, _, l)| l).max());
+        // only the lines grow
+        assert_eq!(galaxy(3, 1).expanded().nth(3), Some((6, 6)));
+    }
 }
@@ -1,4 +1,4 @@
-use std::{fs::read_to_string, usize, collections::HashMap};
+use std::fs::read_to_string;
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,53 +27,23 @@ struct Arrangement {
 
 impl Arrangement {
     fn parse_line(s :&str) -> Option<Self> {
-        let split = s.split_ascii_whitespace().collect::<Vec<&str>>();
+        // either side may be empty : no states, or no groups
+        let mut fields = s.split_ascii_whitespace();
+        let (tiles, counts) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(tiles), Some(counts), None) => (tiles, counts),
+            (Some(field), None, None) if field.chars().all(|c| State::parse(c).is_some()) => (field, ""),
+            (Some(field), None, None) => ("", field),
+            (None, _, _) => ("", ""),
+            _ => return None
+        };
         Some(Self {
-            tiles : split.get(0)?.chars().map(State::parse).collect::<Option<_>>()? ,
-            counts : split.get(1)?.split(',').map(|n| n.parse::<usize>().ok()).collect::<Option<_>>()?
+            tiles : tiles.chars().map(State::parse).collect::<Option<_>>()? ,
+            counts : counts.split(',').filter(|n| !n.is_empty()).map(|n| n.parse::<usize>().ok()).collect::<Option<_>>()?
         })
     }
 
     fn potential_number(&self) -> usize {
-        let running_sumed = self.counts.iter().rev().scan(0 as usize, |state, &n| {
-            if *state != 0 {
-                *state += 1
-            };
-            *state += n;
-            Some(*state)
-        }).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>();
-        solve(
-            &self.tiles,
-            &running_sumed
-        )
-    }
-
-    fn fast_potential_number(&self) -> usize {
-        let running_sumed = self.counts.iter().rev().scan(0 as usize, |state, &n| {
-            if *state != 0 {
-                *state += 1
-            };
-            *state += n;
-            Some(*state)
-        }).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>();
-        fast_solve(
-            &self.tiles,
-            &running_sumed
-        )
-    }
-
-    fn actually_fast_potential_number(&self) -> usize {
-        let running_sumed = self.counts.iter().rev().scan(0 as usize, |state, &n| {
-            if *state != 0 {
-                *state += 1
-            };
-            *state += n;
-            Some(*state)
-        }).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>();
-        actually_fast_solve(
-            &self.tiles,
-            &running_sumed
-        )
+        solve(&self.tiles, &self.counts)
     }
 
     fn unfolded(&self) -> Self {
@@ -89,108 +59,96 @@ impl Arrangement {
     }
 }
 
-//panics if a count is 0
+// ways[i][g] is the number of arrangements of `states[i..]` with the groups `counts[g..]` :
+// either states[i] is operational, or the group g starts there and is followed by an operational state
 fn solve(states : &[State], counts : &[usize]) -> usize {
-    match (counts.get(0), states.len()) {
-        (None, _) => states.iter().all(|x| *x != State::Damaged).then_some(1).unwrap_or(0),
-        (Some(x), y) if y < *x => 0,
-        (Some(x), _) => {
-            let s = states.get(0).expect("only possible if a count is 0");
-            (
-                if *s != State::Damaged { solve(&states[1..], counts) } else { 0 }
-            ) + (
-                if *s != State::Operational { 
-                    if states.iter().take(x - counts.get(1).map(|n| n + 1).unwrap_or(0)).all(|x| *x != State::Operational) && (counts.get(1).map(|n| states[x - n - 1] != State::Damaged).unwrap_or(true)) { 
-                        solve(&states[(x - counts.get(1).map(|n| *n).unwrap_or(0))..], &counts[1..])
-                    } else { 0 }
-                } else { 0 }
-            )
-        },
+    let (n, m) = (states.len(), counts.len());
+    // how many states from i on could all be damaged
+    let mut run = vec![0; n + 1];
+    for i in (0..n).rev() {
+        run[i] = if states[i] != State::Operational { run[i + 1] + 1 } else { 0 };
     }
-}
-
-fn fast_solve(states : &[State], counts : &[usize]) -> usize {
-    let left_index = (counts.len() != 0).then(||(counts.len() - 1) / 2).unwrap_or(0);
-    if counts.len() <= 4 {
-        solve(states, counts)
-    } else {
-        let (left_len, right_len) = (counts[left_index] - counts[left_index + 1] - 1, counts[left_index + 1] - counts[left_index + 2] - 1);
-        let (left_counts, right_counts) = (&counts[..(left_index)].iter().map(|n| *n - counts[left_index] - 1).collect::<Vec<_>>(), &counts[(left_index + 2)..]);
-        let mut res = 0;
-        let (mut left_map, mut right_map) = (HashMap::<usize,usize>::new(), HashMap::<usize,usize>::new());
-        for i in (*left_counts.last().expect("can't be empty"))..(states.len() - counts[left_index].min(states.len())) {
-            if (states[i] != State::Damaged) && states[i + 1..].iter().take(left_len).all(|x| *x != State::Operational) &&  states[i + left_len + 1] != State::Damaged
-            {
-                for j in (i + left_len + 1)..(states.len() - counts[left_index + 1].min(states.len())) {
-                    if states[j] != State::Damaged {
-                        if states[j + 1..].iter().take(right_len).all(|x| *x != State::Operational) &&  states[j + 1 + right_len] != State::Damaged 
-                        {
-                            let left_solve = match left_map.get(&i).copied() {
-                                Some(x) => x,
-                                None => {
-                                    let solve = fast_solve(&states[..i], left_counts);
-                                    left_map.insert(i, solve);
-                                    solve
-                                }
-                            };
-                            let right_solve = match left_map.get(&j).copied() {
-                                Some(x) => x,
-                                None => {
-                                    let solve = fast_solve(&states[j + right_len + 2..], right_counts);
-                                    right_map.insert(j, solve);
-                                    solve
-                                }
-                            };
-
-                            res += left_solve * right_solve
-                        } 
-                    } else {
-                        break;
-                    }
-                }
+    let mut ways = vec![vec![0; m + 1]; n + 1];
+    ways[n][m] = 1;
+    for i in (0..=n).rev() {
+        for g in (0..=m).rev() {
+            if i == n && g == m {
+                continue;
             }
+            ways[i][g] = match counts.get(g) {
+                // an empty group fits anywhere, without taking any room
+                Some(0) => ways[i][g + 1],
+                Some(&len) => {
+                    let operational = if i < n && states[i] != State::Damaged { ways[i + 1][g] } else { 0 };
+                    let damaged = if run[i] >= len && states.get(i + len) != Some(&State::Damaged) { ways[(i + len + 1).min(n)][g + 1] } else { 0 };
+                    operational + damaged
+                },
+                None => if states[i] != State::Damaged { ways[i + 1][g] } else { 0 }
+            };
         }
-        res
     }
+    ways[0][0]
 }
 
-fn actually_fast_solve(states : &[State], counts : &[usize]) -> usize {
-    if counts.len() <= 2{
-        solve(states, counts)
-    } else {
-        let index = counts.len() / 2;
-        let len = counts[index] - counts[index + 1] - 1;
-        let (left_counts, right_counts) = (
-            &counts[..(index)].iter().map(|n| *n - counts[index] - 1).collect::<Vec<_>>(), 
-            &counts[(index + 1)..]
-        );
-        let mut res: usize = 0;
-        for i in (*left_counts.last().expect("can't be empty"))..(states.len() - counts[index].min(states.len())) {
-            if (states[i] != State::Damaged) && states[i + 1..].iter().take(len).all(|x| *x != State::Operational) &&  states[i + len + 1] != State::Damaged {
-                res += fast_solve(&states[..i], left_counts) * fast_solve(&states[i + len + 2..], right_counts)
-            }
-        }
-        res
-    }
-}
 fn main() {
     let arrangements = read_to_string("input.txt").unwrap_or("".to_string()).lines().map(Arrangement::parse_line).collect::<Option<Vec<_>>>().expect("input is malformed");
-    arrangements.iter().map(Arrangement::fast_potential_number).zip(
-        arrangements.iter().map(Arrangement::actually_fast_potential_number)
-    ).enumerate().for_each(|(index,(expected, got))|{
-        if expected != got { println!("at {index}, expected {expected} but got {got}")}
-    });
-
     println!("part 1 : {}", arrangements.iter().map(Arrangement::potential_number).sum::<usize>());
-    println!("part 1 : {}", arrangements.iter().map(Arrangement::fast_potential_number).sum::<usize>());
-    println!("part 2 : {}", arrangements.iter().map(Arrangement::unfolded).enumerate().map(|(n, arr)| {
-        println!("{}", n);
-        /* 
-        let fast = arr.fast_potential_number();
-        let actually = arr.actually_fast_potential_number();
-        if fast != actually {
-            println!("at {n}, expected {fast} but got {actually}")
-        }*/
-        arr.actually_fast_potential_number()
-    }).sum::<usize>());
+    println!("part 2 : {}", arrangements.iter().map(Arrangement::unfolded).map(|arr| arr.potential_number()).sum::<usize>());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(line : &str) -> usize {
+        Arrangement::parse_line(line).unwrap().potential_number()
+    }
+
+    fn states(s : &str) -> Vec<State> {
+        s.chars().map(|c| State::parse(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn example() {
+        let lines = ["???.### 1,1,3", ".??..??...?##. 1,1,3", "?#?#?#?#?#?#?#? 1,3,1,6", "????.#...#... 4,1,1", "????.######..#####. 1,6,5", "?###???????? 3,2,1"];
+        assert_eq!(lines.map(count), [1, 4, 1, 1, 4, 10]);
+        assert_eq!(lines.map(|l| Arrangement::parse_line(l).unwrap().unfolded().potential_number()), [1, 16384, 1, 16, 2500, 506250]);
+    }
+
+    #[test]
+    fn zero_length_groups() {
+        assert_eq!(solve(&states("???"), &[0]), 1);
+        assert_eq!(solve(&states("???"), &[0, 1, 0]), 3);
+        assert_eq!(solve(&states("#.#"), &[1, 0, 1]), 1);
+        assert_eq!(solve(&states("#"), &[0]), 0);
+        assert_eq!(solve(&states(""), &[0, 0]), 1);
+    }
+
+    #[test]
+    fn empty_rows() {
+        assert_eq!(solve(&[], &[]), 1);
+        assert_eq!(solve(&[], &[1]), 0);
+        assert_eq!(solve(&states("?.?"), &[]), 1);
+        assert_eq!(solve(&states("?#"), &[]), 0);
+        assert_eq!(count(""), 1);
+        assert_eq!(count(" 1"), 0);
+        assert_eq!(count("???"), 1);
+    }
+
+    #[test]
+    fn groups_filling_the_row() {
+        assert_eq!(solve(&states("???"), &[3]), 1);
+        assert_eq!(solve(&states("???"), &[1, 1]), 1);
+        assert_eq!(solve(&states("???"), &[4]), 0);
+        assert_eq!(solve(&states("???"), &[2, 1]), 0);
+    }
+
+    #[test]
+    fn any_whitespace_between_fields() {
+        assert_eq!(count("???.###  1,1,3"), 1);
+        assert_eq!(count("???.###\t1,1,3"), 1);
+        assert_eq!(count("  ???.### 1,1,3  "), 1);
+        assert!(Arrangement::parse_line("???.### 1,1,3 extra").is_none());
+        assert!(Arrangement::parse_line("???.x 1").is_none());
+    }
 }
@@ -1,6 +1,6 @@
 use std::{fs::read_to_string, collections::HashMap, ops::BitXor};
 
-use itertools::Itertools;
+use iter_ext::IterExt;
 
 #[derive(Debug, Clone, Copy)]
 struct Line {
@@ -90,7 +90,7 @@ fn symmetric_distance(index : usize, vec : &Vec<Line>) -> Option<u64> {
 
 fn main() {
 
-    let u = read_to_string("input.txt").unwrap_or("".to_string()).lines().group_by(|l| *l != "").into_iter().filter_map(|(b, v)| b.then_some(v)).map(Terrain::parse).collect::<Option<Vec<_>>>().unwrap();
+    let u = read_to_string("input.txt").unwrap_or("".to_string()).lines().split_on(|l| l.is_empty()).filter(|v| !v.is_empty()).map(|v| Terrain::parse(v.into_iter())).collect::<Option<Vec<_>>>().unwrap();
     println!("part 1 : {}", u.iter().map(Terrain::summarize).sum::<usize>());
     println!("part 2 : {}", u.iter().map(Terrain::almost_summarize).sum::<usize>());
 }
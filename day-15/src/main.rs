@@ -1,6 +1,6 @@
 use std::{fs::read_to_string, collections::HashMap};
 
-use itertools::Itertools;
+use iter_ext::IterExt;
 
 #[derive(Debug, Clone)]
 enum SignType {
@@ -49,9 +49,9 @@ impl Sign {
 
 
 fn main() {
-    println!("part 1 : {}", read_to_string("input.txt").unwrap().bytes().group_by(|c| *c != b',' && *c != b'\n').into_iter().filter_map(|(b,v)| b.then_some(v).map(|iter|iter.fold::<u8,_>(0, |acc, v| acc.wrapping_add(v).wrapping_mul(17)) as u64)).sum::<u64>());
+    println!("part 1 : {}", read_to_string("input.txt").unwrap().bytes().split_on(|c| *c == b',' || *c == b'\n').filter(|v| !v.is_empty()).map(|v| v.into_iter().fold::<u8,_>(0, |acc, v| acc.wrapping_add(v).wrapping_mul(17)) as u64).sum::<u64>());
 
-    let signs = read_to_string("input.txt").unwrap().bytes().group_by(|c| *c != b',' && *c != b'\n').into_iter().filter_map(|(b,v)| b.then_some(v).map(Sign::parse)).collect::<Option<Vec<_>>>().unwrap();
+    let signs = read_to_string("input.txt").unwrap().bytes().split_on(|c| *c == b',' || *c == b'\n').filter(|v| !v.is_empty()).map(|v| Sign::parse(v.into_iter())).collect::<Option<Vec<_>>>().unwrap();
     println!("part 2 : {}", signs.into_iter().fold(HashMap::<u8, Vec<Sign>>::new(), |mut map, s| {
         match s.sgn_type {
             SignType::Dash => {
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, fs::read_to_string, iter::repeat};
+use std::{collections::HashSet, env::args, fs::read_to_string};
 use regex::{Regex, Match};
 
 
@@ -15,10 +15,10 @@ fn get_all_numbers(m : Option<Match>) -> Option<HashSet<u64>> {
 impl Card {
     fn from_line(str : &str) -> Option<Self> {
         let capt = Regex::new("^Card[ ]+([0-9]+): ([^|]*) \\| (.*)$").unwrap().captures(str)?;
-        Some(Self { 
-            index: capt.get(1).and_then(|s| s.as_str().parse::<u64>().ok())?, 
-            input: get_all_numbers(capt.get(2))?, 
-            output: get_all_numbers(capt.get(3))? 
+        Some(Self {
+            index: capt.get(1).and_then(|s| s.as_str().parse::<u64>().ok())?,
+            input: get_all_numbers(capt.get(2))?,
+            output: get_all_numbers(capt.get(3))?
         })
     }
 
@@ -26,59 +26,174 @@ impl Card {
         self.input.intersection(&self.output).count() as u64
     }
 
-    fn points(&self) -> u64 {
-        match self.matches() {
+    fn points<S : ScoringRule + ?Sized>(&self, rule : &S) -> u64 {
+        rule.score(self.matches())
+    }
+}
+
+trait ScoringRule {
+    fn score(&self, matches : u64) -> u64;
+}
+
+// 1, 2, 4, 8, ...
+struct Doubling;
+
+// 1, 2, 3, 4, ...
+struct Linear;
+
+// 1, 1, 2, 3, 5, ...
+struct Fibonacci;
+
+impl ScoringRule for Doubling {
+    fn score(&self, matches : u64) -> u64 {
+        match matches {
             0 => 0,
-            n => (2 as u64).pow(n as u32 - 1)
+            n => 2_u64.pow(n as u32 - 1)
         }
     }
 }
 
-struct InterSum<I,J>
-where
-    I : Iterator<Item = u64>,
-    J : Iterator<Item = u64>
-{
-    i : I,
-    j : J
+impl ScoringRule for Linear {
+    fn score(&self, matches : u64) -> u64 {
+        matches
+    }
+}
+
+impl ScoringRule for Fibonacci {
+    fn score(&self, matches : u64) -> u64 {
+        (0..matches).fold((0, 1), |(a, b) : (u64, u64), _| (b, a + b)).0
+    }
+}
+
+trait CopyRule {
+    // positions of the cards won by the card at `position`, among `len` cards
+    fn won(&self, position : usize, matches : u64, len : usize) -> Vec<usize>;
+}
+
+// the original rule : one copy of each of the next `matches` cards
+struct NextCards;
+
+// like `NextCards`, but a card never wins more than `cap` cards
+struct Capped {
+    cap : u64
+}
+
+// like `NextCards`, but going past the last card starts again from the first,
+// stopping before the card comes back to itself
+struct Wrapping;
+
+impl CopyRule for NextCards {
+    fn won(&self, position : usize, matches : u64, len : usize) -> Vec<usize> {
+        (position + 1..len).take(matches as usize).collect()
+    }
+}
+
+impl CopyRule for Capped {
+    fn won(&self, position : usize, matches : u64, len : usize) -> Vec<usize> {
+        NextCards.won(position, matches.min(self.cap), len)
+    }
 }
 
-impl<I, J> InterSum<I, J>
-where
-    I : Iterator<Item = u64>,
-    J : Iterator<Item = u64> 
-{
-    fn new(i : I, j : J) -> Self {
-        Self { i, j }
+impl CopyRule for Wrapping {
+    fn won(&self, position : usize, matches : u64, len : usize) -> Vec<usize> {
+        (1..=(matches as usize).min(len - 1)).map(|i| (position + i) % len).collect()
     }
 }
 
-impl<I, J> Iterator for InterSum<I, J>
-where
-    I : Iterator<Item = u64>,
-    J : Iterator<Item = u64> 
-{
-    type Item = u64;
-    fn next(&mut self) -> Option<Self::Item> {
-        match (self.i.next(), self.j.next()) {
-            (None, None) => None,
-            (n, m) => Some(n.unwrap_or(0) + m.unwrap_or(0))
+// cards are resolved once, in order : copies won on an already resolved card
+// (which only `Wrapping` can produce) are counted, but do not win anything themselves
+fn total_cards<C : CopyRule + ?Sized>(cards : &[Card], rule : &C) -> u64 {
+    let mut copies = vec![1; cards.len()];
+    for (position, card) in cards.iter().enumerate() {
+        for won in rule.won(position, card.matches(), cards.len()) {
+            copies[won] += copies[position];
         }
     }
+    copies.into_iter().sum()
 }
 
+fn parse_scoring(name : &str) -> Option<Box<dyn ScoringRule>> {
+    match name {
+        "doubling" => Some(Box::new(Doubling)),
+        "linear" => Some(Box::new(Linear)),
+        "fibonacci" => Some(Box::new(Fibonacci)),
+        _ => None
+    }
+}
+
+fn parse_copies(name : &str) -> Option<Box<dyn CopyRule>> {
+    match name.split_once(':') {
+        None if name == "next" => Some(Box::new(NextCards)),
+        None if name == "wrap" => Some(Box::new(Wrapping)),
+        Some(("capped", cap)) => Some(Box::new(Capped { cap: cap.parse().ok()? })),
+        _ => None
+    }
+}
+
+// usage : day-4-1 [doubling|linear|fibonacci] [next|capped:N|wrap]
 fn main() {
-    println!("part 1 :{}", read_to_string("input.txt")
+    let mut args = args().skip(1);
+    let scoring = args.next().map_or(Some(Box::new(Doubling) as Box<dyn ScoringRule>), |s| parse_scoring(&s))
+        .expect("unknown scoring rule, expected doubling, linear or fibonacci");
+    let copies = args.next().map_or(Some(Box::new(NextCards) as Box<dyn CopyRule>), |s| parse_copies(&s))
+        .expect("unknown copy rule, expected next, capped:N or wrap");
+    let cards = read_to_string("input.txt")
     .unwrap_or("".to_string())
-    .lines().filter_map(Card::from_line).map(|c|c.points()).sum::<u64>());
-    println!("part 2 :{}", {let (n, _) = read_to_string("input.txt")
-    .unwrap_or("".to_string())
-    .lines().filter_map(Card::from_line).map(|c|c.matches()).fold((0, Vec::<u64>::new()), |acc: (u64, Vec<u64>), n| {
-        let (res, vec) = acc;
-        println!("{:?}",vec);
-        let mut iter = vec.into_iter();
-        let cardnb = 1 + iter.next().unwrap_or(0);
-        (res + cardnb, InterSum::new(repeat(cardnb).take(n.try_into().unwrap()),iter).collect::<Vec<_>>())
-
-    }); n})
+    .lines().filter_map(Card::from_line).collect::<Vec<_>>();
+    println!("part 1 :{}", cards.iter().map(|c|c.points(scoring.as_ref())).sum::<u64>());
+    println!("part 2 :{}", total_cards(&cards, copies.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(index : u64, input : &[u64], output : &[u64]) -> Card {
+        Card { index, input: input.iter().copied().collect(), output: output.iter().copied().collect() }
+    }
+
+    // one card per number of matches
+    fn cards(matches : &[u64]) -> Vec<Card> {
+        matches.iter().enumerate().map(|(i, &m)| card(i as u64 + 1, &(0..m).collect::<Vec<_>>(), &(0..10).collect::<Vec<_>>())).collect()
+    }
+
+    #[test]
+    fn scoring_rules() {
+        assert_eq!((0..6).map(|m| Doubling.score(m)).collect::<Vec<_>>(), vec![0, 1, 2, 4, 8, 16]);
+        assert_eq!((0..6).map(|m| Linear.score(m)).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!((0..6).map(|m| Fibonacci.score(m)).collect::<Vec<_>>(), vec![0, 1, 1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn points_count_matches() {
+        let c = Card::from_line("Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53").unwrap();
+        assert_eq!(c.index, 1);
+        assert_eq!(c.matches(), 4);
+        assert_eq!(c.points(&Doubling), 8);
+    }
+
+    #[test]
+    fn copy_rules() {
+        assert_eq!(NextCards.won(1, 2, 5), vec![2, 3]);
+        assert_eq!(NextCards.won(3, 4, 5), vec![4]);
+        assert_eq!(Capped { cap: 1 }.won(1, 3, 5), vec![2]);
+        assert_eq!(Wrapping.won(3, 3, 5), vec![4, 0, 1]);
+    }
+
+    #[test]
+    fn wrapping_never_wins_itself() {
+        assert_eq!(Wrapping.won(1, 10, 3), vec![2, 0]);
+        assert_eq!(Wrapping.won(0, 5, 1), vec![]);
+    }
+
+    #[test]
+    fn total_cards_per_rule() {
+        // the example of the puzzle
+        let example = cards(&[4, 2, 2, 1, 0, 0]);
+        assert_eq!(total_cards(&example, &NextCards), 30);
+        assert_eq!(total_cards(&example, &Capped { cap: 1 }), 1 + 2 + 3 + 4 + 5 + 1);
+        // the last card wins the first, which is already resolved
+        assert_eq!(total_cards(&cards(&[0, 0, 1]), &Wrapping), 4);
+        assert_eq!(total_cards(&cards(&[5, 0]), &Wrapping), 3);
+    }
 }
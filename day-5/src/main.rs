@@ -1,31 +1,20 @@
-use std::fs::read_to_string;
+use std::{collections::{HashMap, HashSet, VecDeque}, env::args, fmt::{self, Display, Formatter}, fs::read_to_string};
+use interval_set::{IntervalSet, Range};
 use regex::Regex;
 
-#[derive(Debug)]
-struct Range {
-    start : u64,
-    end : u64
-}
-
-impl Range {
-    fn new(start : u64, end : u64) -> Self {
-        Self { start, end }
-    }
-}
-
-fn ranges_from_nbs<T>(iter :&mut T) -> Vec<Range>
+fn ranges_from_nbs<T>(iter :&mut T) -> IntervalSet
 where
     T : Iterator<Item=u64>
 {
-    let mut vec = Vec::<Range>::new();
+    let mut set = IntervalSet::new();
     let mut maybe_start = iter.next();
     let mut maybe_len = iter.next();
     while let (Some(start), Some(len)) = (maybe_start, maybe_len) {
-        vec.push(Range::new(start, start + len));
+        set.insert(Range::new(start, start + len));
         maybe_start = iter.next();
         maybe_len = iter.next();
     };
-    vec
+    set
 }
 
 struct MapRange {
@@ -46,6 +35,10 @@ impl MapRange {
         (n - self.start) + self.mapped_start
     }
 
+    fn invert(&self) -> Self {
+        Self { start: self.mapped_start, end: self.mapped_start + (self.end - self.start), mapped_start: self.start }
+    }
+
     fn apply_range(&self, r : &Range) -> Option<Range> {
         (r.start < self.end && r.end > self.start).then(||
             Range::new(
@@ -60,25 +53,13 @@ struct Mapping {
     maps : Vec<MapRange>
 }
 
-fn collect_ranges<I>(iter :&mut I) -> Vec<Range>
-where
-    I : Iterator<Item=Range>,
-{
-    iter.fold(Vec::<Range>::new(), |mut vec, r| {
-        let modified_index = match vec.binary_search_by_key(&r.start, |vec_r|vec_r.start) {
-            Ok(i) => {if vec[i].end < r.end {vec[i] = r;};i},
-            Err(0) => {vec.insert(0, r);0},
-            Err(i) => if vec[i-1].end >= r.start {
-                vec[i-1] = Range::new(vec[i-1].start, vec[i-1].end.max(r.end));
-                i-1
-            } else {vec.insert(i, r);i}
-        };
-        if modified_index + 1 < vec.len() && vec[modified_index].end >= vec[modified_index+1].start {
-            let r = vec.remove(modified_index + 1);
-            vec[modified_index] = Range::new(vec[modified_index].start, vec[modified_index].end.max(r.end))
+impl Display for Mapping {
+    fn fmt(&self, f : &mut Formatter<'_>) -> fmt::Result {
+        for mr in &self.maps {
+            writeln!(f, "{} {} {}", mr.mapped_start, mr.start, mr.end - mr.start)?;
         };
-        vec
-    })
+        Ok(())
+    }
 }
 
 impl Mapping {
@@ -92,68 +73,355 @@ impl Mapping {
         Self { maps }
     }
 
-    fn apply(&self, vec : &Vec<u64>) -> Vec<u64> {
-        vec.iter().map(
-            |n| {
-                match self.maps.binary_search_by_key(n, |mr| mr.start) {
-                    Ok(i) => Some(i),
-                    Err(0) => None,
-                    Err(i) =>  (*n < self.maps[i - 1].end).then_some(i - 1)
-                }.map(|i| self.maps[i].apply(*n)).unwrap_or(*n)
+    fn apply(&self, n : u64) -> u64 {
+        match self.maps.binary_search_by_key(&n, |mr| mr.start) {
+            Ok(i) => Some(i),
+            Err(0) => None,
+            Err(i) =>  (n < self.maps[i - 1].end).then_some(i - 1)
+        }.map(|i| self.maps[i].apply(n)).unwrap_or(n)
+    }
+
+    // uncovered parts of the ranges are kept as is, like in `apply`
+    fn apply_range(&self, set : &IntervalSet) -> IntervalSet {
+        set.iter().flat_map(
+            |r| self.maps.iter().filter_map(|mr| mr.apply_range(r)).chain(self.gaps(r))
+        ).collect()
+    }
+
+    // parts of `r` not covered by any map range, which map to themselves
+    fn gaps(&self, r : &Range) -> IntervalSet {
+        self.maps.iter().map(|mr| Range::new(mr.start, mr.end)).collect::<IntervalSet>().complement(*r)
+    }
+
+    // a single mapping equivalent to applying `self`, then `next`
+    fn compose(&self, next : &Mapping) -> Mapping {
+        let everything = Range::new(0, u64::MAX);
+        let segments = self.maps.iter().map(|mr| (Range::new(mr.start, mr.end), mr.mapped_start))
+            .chain(self.gaps(&everything).into_iter().map(|r| {let start = r.start; (r, start)}));
+        let mut maps = vec![];
+        for (source, mapped_start) in segments {
+            let image = Range::new(mapped_start, mapped_start + (source.end - source.start));
+            let covered = next.maps.iter().filter(|mr| mr.start < image.end && image.start < mr.end)
+                .map(|mr| {let start = mr.start.max(image.start); (Range::new(start, mr.end.min(image.end)), mr.apply(start))});
+            let uncovered = next.gaps(&image).into_iter().map(|r| {let start = r.start; (r, start)});
+            for (piece, target) in covered.chain(uncovered) {
+                let start = source.start + (piece.start - image.start);
+                maps.push(MapRange { start, end: start + (piece.end - piece.start), mapped_start: target });
             }
+        };
+        maps.retain(|mr| mr.start != mr.mapped_start);
+        maps.sort_by_key(|mr| mr.start);
+        let maps = maps.into_iter().fold(Vec::<MapRange>::new(), |mut maps, mr| {
+            match maps.last_mut() {
+                Some(last) if last.end == mr.start && last.apply(last.end - 1) + 1 == mr.mapped_start => last.end = mr.end,
+                _ => maps.push(mr)
+            };
+            maps
+        });
+        Mapping { maps }
+    }
+
+    // every range of inputs whose output falls in `set`
+    fn preimage_range(&self, set : &IntervalSet) -> IntervalSet {
+        set.iter().flat_map(
+            |r| self.maps.iter().filter_map(|mr| mr.invert().apply_range(r)).chain(self.gaps(r))
         ).collect()
     }
+}
 
-    fn apply_range(&self, vec : &Vec<Range>) -> Vec<Range> {
-        collect_ranges(&mut vec.iter().map(
-            |r| self.maps.iter().filter_map(|mr| mr.apply_range(r))
-        ).flatten())
+#[derive(Debug)]
+enum AlmanacError {
+    MissingSeeds,
+    BadHeader(String),
+    Disconnected(Vec<String>),
+    NoPath(String, String)
+}
+
+impl Display for AlmanacError {
+    fn fmt(&self, f : &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AlmanacError::MissingSeeds => write!(f, "the first line should list the seeds"),
+            AlmanacError::BadHeader(line) => write!(f, "expected a map header, found \"{}\"", line),
+            AlmanacError::Disconnected(categories) => write!(f, "categories not reachable from seed : {}", categories.join(", ")),
+            AlmanacError::NoPath(from, to) => write!(f, "no chain of maps leads from {} to {}", from, to)
+        }
     }
 }
 
-struct Instructions<'a> {
-    values : Vec<(&'a str, Vec<u64>)>
+trait Convertible {
+    fn convert(self, mapping : &Mapping) -> Self;
 }
 
-impl<'a> Instructions<'a> {
-    fn new<T>(iter :&mut T) -> Self
-    where
-        T : Iterator<Item=&'a str>,
-    {
-        let mut values = vec![("seeds", iter.next().map(|s| s.split_ascii_whitespace().filter_map(|s| s.parse::<u64>().ok()).collect::<Vec<_>>()).unwrap_or(vec![]))];
-        iter.next();
-        while let Some(next_name) = iter.next().and_then(|s| Regex::new("([a-z]+)-to-([a-z]+) map:").unwrap().captures(s)).and_then(|c| c.get(2)).map(|m| m.as_str()) {
-            let next_values = Mapping::new(iter).apply({let (_, v) = values.last().unwrap(); v});
-            values.push((next_name, next_values));
-        };
-        Instructions { values }
+impl Convertible for u64 {
+    fn convert(self, mapping : &Mapping) -> Self {
+        mapping.apply(self)
     }
 }
 
-struct InstructionsRanges<'a> {
-    values : Vec<(&'a str, Vec<Range>)>
+impl Convertible for Vec<u64> {
+    fn convert(self, mapping : &Mapping) -> Self {
+        self.into_iter().map(|n| mapping.apply(n)).collect()
+    }
 }
 
-impl<'a> InstructionsRanges<'a> {
-    fn new<T>(iter :&mut T) -> Self
-    where
-        T : Iterator<Item=&'a str>,
-    {
-        let mut values = vec![("seeds", iter.next().map(|s| ranges_from_nbs(&mut s.split_ascii_whitespace().filter_map(|s| s.parse::<u64>().ok()))).unwrap_or(vec![]))];
-        iter.next();
-        while let Some(next_name) = iter.next().and_then(|s| Regex::new("([a-z]+)-to-([a-z]+) map:").unwrap().captures(s)).and_then(|c| c.get(2)).map(|m| m.as_str()) {
-            let next_values = Mapping::new(iter).apply_range({let (_, v) = values.last().unwrap(); v});
-            values.push((next_name, next_values));
+impl Convertible for IntervalSet {
+    fn convert(self, mapping : &Mapping) -> Self {
+        mapping.apply_range(&self)
+    }
+}
+
+struct Almanac<'a> {
+    seeds : Vec<u64>,
+    maps : HashMap<(&'a str, &'a str), Mapping>
+}
+
+impl<'a> Almanac<'a> {
+    fn new(input : &'a str) -> Result<Self, AlmanacError> {
+        let mut iter = input.lines();
+        let seeds = iter.next().and_then(|s| s.strip_prefix("seeds:")).ok_or(AlmanacError::MissingSeeds)?
+            .split_ascii_whitespace().filter_map(|s| s.parse::<u64>().ok()).collect::<Vec<_>>();
+        let header = Regex::new("^([a-z]+)-to-([a-z]+) map:$").unwrap();
+        let mut maps = HashMap::new();
+        while let Some(line) = iter.next() {
+            if line.is_empty() {
+                continue;
+            }
+            let capt = header.captures(line).ok_or_else(|| AlmanacError::BadHeader(line.to_string()))?;
+            let (_, [source, destination]) = capt.extract();
+            maps.insert((source, destination), Mapping::new(&mut iter));
+        };
+        let almanac = Self { seeds, maps };
+        almanac.check_connected()?;
+        Ok(almanac)
+    }
+
+    fn categories(&self) -> HashSet<&'a str> {
+        self.maps.keys().flat_map(|&(source, destination)| [source, destination]).chain(["seed"]).collect()
+    }
+
+    // every category reachable from `from` by following maps forward, with the category it is reached from
+    fn reachable<'b>(&'b self, from : &'b str) -> HashMap<&'b str, &'b str> {
+        let mut previous = HashMap::<&str, &str>::new();
+        let mut todo = VecDeque::from([from]);
+        while let Some(category) = todo.pop_front() {
+            for &(source, destination) in self.maps.keys() {
+                if source == category && destination != from && !previous.contains_key(destination) {
+                    previous.insert(destination, source);
+                    todo.push_back(destination);
+                }
+            }
         };
-        InstructionsRanges { values }
+        previous
+    }
+
+    // every category must be reachable from seeds the way `path` goes
+    fn check_connected(&self) -> Result<(), AlmanacError> {
+        let reached = self.reachable("seed");
+        let mut missing = self.categories().into_iter().filter(|c| *c != "seed" && !reached.contains_key(c))
+            .map(|s| s.to_string()).collect::<Vec<_>>();
+        missing.sort();
+        missing.is_empty().then_some(()).ok_or(AlmanacError::Disconnected(missing))
+    }
+
+    // shortest chain of maps leading from `from` to `to`
+    fn path<'b>(&'b self, from : &'b str, to : &'b str) -> Result<Vec<&'b Mapping>, AlmanacError> {
+        let previous = self.reachable(from);
+        let mut path = vec![];
+        let mut current = to;
+        while current != from {
+            let source = previous.get(current).ok_or_else(|| AlmanacError::NoPath(from.to_string(), to.to_string()))?;
+            path.push(&self.maps[&(*source, current)]);
+            current = source;
+        };
+        path.reverse();
+        Ok(path)
+    }
+
+    fn preimage(&self, from : &str, to : &str, ranges : IntervalSet) -> Result<IntervalSet, AlmanacError> {
+        Ok(self.path(from, to)?.into_iter().rev().fold(ranges, |ranges, mapping| mapping.preimage_range(&ranges)))
+    }
+
+    // searches locations upward for the lowest one reached by one of the `seeds`
+    fn lowest_location_from(&self, seeds : &IntervalSet) -> Result<Option<u64>, AlmanacError> {
+        let reached = |end : u64| self.preimage("seed", "location", IntervalSet::from_iter([Range::new(0, end)]))
+            .map(|found| !found.intersection(seeds).is_empty());
+        if !reached(u64::MAX)? {
+            return Ok(None);
+        }
+        let (mut low, mut high) = (0, u64::MAX);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if reached(mid)? {high = mid} else {low = mid}
+        };
+        Ok(Some(low))
+    }
+
+    fn compose(&self, from : &str, to : &str) -> Result<Mapping, AlmanacError> {
+        Ok(self.path(from, to)?.into_iter().fold(Mapping { maps: vec![] }, |composed, mapping| composed.compose(mapping)))
+    }
+
+    fn convert<T : Convertible>(&self, from : &str, to : &str, value : T) -> Result<T, AlmanacError> {
+        Ok(self.path(from, to)?.into_iter().fold(value, |value, mapping| value.convert(mapping)))
     }
 }
 
 fn main() {
-    println!("part 1 : {}", Instructions::new(&mut read_to_string("input.txt")
-    .unwrap_or("".to_string())
-    .lines()).values.last().unwrap().1.iter().min().unwrap_or(&0));
-    println!("part 2 : {}", InstructionsRanges::new(&mut read_to_string("input.txt")
-    .unwrap_or("".to_string())
-    .lines()).values.last().unwrap().1.iter().map(|r| r.start).min().unwrap_or(0));
+    let input = read_to_string("input.txt").unwrap_or("".to_string());
+    let almanac = match Almanac::new(&input) {
+        Ok(almanac) => almanac,
+        Err(e) => {eprintln!("{}", e); return}
+    };
+    match almanac.compose("seed", "location") {
+        Ok(composed) => {
+            if args().any(|arg| arg == "--table") {
+                print!("seed-to-location map:\n{}", composed);
+            }
+            println!("part 1 : {}", almanac.seeds.iter().map(|&seed| composed.apply(seed)).min().unwrap_or(0))
+        },
+        Err(e) => eprintln!("{}", e)
+    };
+    let seed_ranges = ranges_from_nbs(&mut almanac.seeds.iter().copied());
+    match almanac.convert("seed", "location", seed_ranges.clone()) {
+        Ok(locations) => println!("part 2 : {}", locations.iter().next().map_or(0, |r| r.start)),
+        Err(e) => eprintln!("{}", e)
+    };
+    match almanac.lowest_location_from(&seed_ranges) {
+        Ok(location) => println!("part 2 (from locations) : {}", location.unwrap_or(0)),
+        Err(e) => eprintln!("{}", e)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 10..20 goes to 100..110, and 30..35 to 0..5
+    fn mapping() -> Mapping {
+        Mapping::new(&mut ["100 10 10", "0 30 5"].into_iter())
+    }
+
+    fn set(ranges : &[(u64, u64)]) -> IntervalSet {
+        ranges.iter().map(|&(start, end)| Range::new(start, end)).collect()
+    }
+
+    #[test]
+    fn apply_range_overhanging_on_the_left() {
+        assert_eq!(mapping().apply_range(&set(&[(5, 15)])), set(&[(5, 10), (100, 105)]));
+    }
+
+    #[test]
+    fn apply_range_overhanging_on_the_right() {
+        assert_eq!(mapping().apply_range(&set(&[(15, 25)])), set(&[(20, 25), (105, 110)]));
+    }
+
+    #[test]
+    fn apply_range_overhanging_on_both_sides() {
+        assert_eq!(mapping().apply_range(&set(&[(5, 25)])), set(&[(5, 10), (20, 25), (100, 110)]));
+        assert_eq!(mapping().apply_range(&set(&[(15, 40)])), set(&[(0, 5), (20, 30), (35, 40), (105, 110)]));
+    }
+
+    #[test]
+    fn apply_range_without_overlap() {
+        assert_eq!(mapping().apply_range(&set(&[(0, 10)])), set(&[(0, 10)]));
+        assert_eq!(mapping().apply_range(&set(&[(20, 30)])), set(&[(20, 30)]));
+        assert_eq!(mapping().apply_range(&set(&[(12, 18)])), set(&[(102, 108)]));
+    }
+
+    #[test]
+    fn apply_range_agrees_with_apply() {
+        let m = mapping();
+        let mapped = m.apply_range(&set(&[(0, 50)]));
+        assert!((0..50).all(|n| mapped.contains(m.apply(n))));
+    }
+
+    const EXAMPLE : &str = concat!(
+        "seeds: 79 14 55 13\n\n",
+        "seed-to-soil map:\n50 98 2\n52 50 48\n\n",
+        "soil-to-fertilizer map:\n0 15 37\n37 52 2\n39 0 15\n\n",
+        "fertilizer-to-water map:\n49 53 8\n0 11 42\n42 0 7\n57 7 4\n\n",
+        "water-to-light map:\n88 18 7\n18 25 70\n\n",
+        "light-to-temperature map:\n45 77 23\n81 45 19\n68 64 13\n\n",
+        "temperature-to-humidity map:\n0 69 1\n1 0 69\n\n",
+        "humidity-to-location map:\n60 56 37\n56 93 4\n"
+    );
+
+    #[test]
+    fn preimage_range_agrees_with_apply() {
+        let m = mapping();
+        for target in [set(&[(100, 105)]), set(&[(0, 3), (22, 31), (107, 120)]), set(&[])] {
+            let preimage = m.preimage_range(&target);
+            for x in 0..150 {
+                assert_eq!(preimage.contains(x), target.contains(m.apply(x)), "{} in the preimage of {:?}", x, target);
+            }
+        }
+    }
+
+    #[test]
+    fn preimage_through_the_almanac() {
+        let almanac = Almanac::new(EXAMPLE).unwrap();
+        let seeds = almanac.preimage("seed", "location", set(&[(46, 47)])).unwrap();
+        assert!(seeds.contains(82));
+        assert!(seeds.iter().flat_map(|r| r.start..r.end).all(|seed| almanac.convert("seed", "location", seed).unwrap() == 46));
+    }
+
+    #[test]
+    fn lowest_location_from_seeds() {
+        let almanac = Almanac::new(EXAMPLE).unwrap();
+        let seed_ranges = ranges_from_nbs(&mut almanac.seeds.iter().copied());
+        assert_eq!(almanac.lowest_location_from(&seed_ranges).unwrap(), Some(46));
+        assert_eq!(almanac.lowest_location_from(&set(&[(79, 80)])).unwrap(), Some(82));
+        assert_eq!(almanac.lowest_location_from(&set(&[(u64::MAX - 1, u64::MAX)])).unwrap(), Some(u64::MAX - 1));
+        // no seeds, no location
+        assert_eq!(almanac.lowest_location_from(&IntervalSet::new()).unwrap(), None);
+    }
+
+    #[test]
+    fn compose_agrees_with_applying_in_turn() {
+        let (a, b) = (mapping(), Mapping::new(&mut ["5 100 3", "200 0 20", "40 104 10"].into_iter()));
+        for (first, second) in [(&a, &b), (&b, &a), (&a, &a)] {
+            let composed = first.compose(second);
+            for x in 0..300 {
+                assert_eq!(composed.apply(x), second.apply(first.apply(x)), "composed at {}", x);
+            }
+        }
+    }
+
+    #[test]
+    fn almanac_compose_agrees_with_convert() {
+        let almanac = Almanac::new(EXAMPLE).unwrap();
+        let composed = almanac.compose("seed", "location").unwrap();
+        for seed in 0..200 {
+            assert_eq!(composed.apply(seed), almanac.convert("seed", "location", seed).unwrap());
+        }
+    }
+
+    #[test]
+    fn compose_merges_adjacent_segments() {
+        let none = Mapping::new(&mut [].into_iter());
+        // 10..15 to 20..25 and 15..20 to 25..30 are one shift
+        let split = Mapping::new(&mut ["20 10 5", "25 15 5"].into_iter());
+        assert_eq!(split.compose(&none).to_string(), "20 10 10\n");
+        // there and back again is identity on 0..10, while 50..60, untouched at first, still goes back
+        let there = Mapping::new(&mut ["50 0 10"].into_iter());
+        let back = Mapping::new(&mut ["0 50 10"].into_iter());
+        assert_eq!(there.compose(&back).to_string(), "0 50 10\n");
+        assert_eq!(Mapping::new(&mut ["10 10 5"].into_iter()).compose(&none).to_string(), "");
+    }
+
+    #[test]
+    fn categories_only_reachable_backwards_are_rejected() {
+        let input = "seeds: 1\n\nseed-to-soil map:\n0 0 10\n\nwater-to-soil map:\n0 0 10\n";
+        match Almanac::new(input) {
+            Err(AlmanacError::Disconnected(missing)) => assert_eq!(missing, vec!["water".to_string()]),
+            _ => panic!("water can not be reached from seed")
+        }
+    }
+
+    #[test]
+    fn maps_are_followed_forward() {
+        let input = "seeds: 1\n\nseed-to-soil map:\n10 0 5\n\nsoil-to-water map:\n100 10 5\n";
+        let almanac = Almanac::new(input).unwrap();
+        assert_eq!(almanac.convert("seed", "water", 2_u64).unwrap(), 102);
+        assert!(matches!(almanac.convert("water", "seed", 102_u64), Err(AlmanacError::NoPath(_, _))));
+    }
 }
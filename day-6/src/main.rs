@@ -1,38 +1,360 @@
+use std::{env::args, fmt::{self, Display, Formatter}, fs::read_to_string, ops::RangeInclusive};
+
 
 struct Race {
-    time : usize,
-    distance : usize
+    time : u128,
+    distance : u128
 }
 
 impl Race {
-    fn lazy_ways_to_beat(&self) -> usize {
-        (0..=self.time).filter_map(|x| {
-            (x * (self.time - x) >= self.distance).then_some(0)
-        }).count()
+    // a product too big for u128 is bigger than any record
+    fn beats(&self, hold : u128) -> bool {
+        hold.checked_mul(self.time - hold).is_none_or(|d| d > self.distance)
+    }
+
+    #[cfg(test)]
+    fn lazy_ways_to_beat(&self) -> u128 {
+        (0..=self.time).filter(|&x| self.beats(x)).count() as u128
+    }
+
+    // shortest winning hold : the smallest x with x * (time - x) > distance,
+    // found from the integer root of time² - 4 * distance then corrected by hand
+    fn shortest_win(&self) -> Option<u128> {
+        let half = self.time / 2;
+        if !self.beats(half) {
+            return None;
+        }
+        let mut x = match self.time.checked_mul(self.time).zip(self.distance.checked_mul(4)).and_then(|(t2, d4)| t2.checked_sub(d4)) {
+            Some(delta) => (self.time - delta.isqrt()) / 2,
+            None => {
+                let (mut low, mut high) = (0, half);
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    if self.beats(mid) {high = mid} else {low = mid + 1}
+                };
+                low
+            }
+        };
+        while x > 0 && self.beats(x - 1) {
+            x -= 1;
+        };
+        while !self.beats(x) {
+            x += 1;
+        };
+        Some(x)
+    }
+
+    #[cfg(test)]
+    fn fast_ways_to_beat(&self) -> u128 {
+        self.shortest_win().map_or(0, |x| self.time - 2 * x + 1)
+    }
+}
+
+trait BoatModel {
+    // distance covered by holding the button for `hold` out of `time` milliseconds,
+    // None if it does not fit in a u128 : further than any record
+    fn distance(&self, hold : u128, time : u128) -> Option<u128>;
+
+    fn beats(&self, hold : u128, race : &Race) -> bool {
+        self.distance(hold, race.time).is_none_or(|d| d > race.distance)
+    }
+
+    // a hold going the furthest : distance never decreases before it, nor increases after
+    fn best_hold(&self, time : u128) -> u128;
+
+    fn winning_holds(&self, race : &Race) -> Option<RangeInclusive<u128>> {
+        monotone_search(self, race)
+    }
+
+    fn ways_to_beat(&self, race : &Race) -> u128 {
+        self.winning_holds(race).map_or(0, |holds| holds.end() - holds.start() + 1)
+    }
+
+    #[cfg(test)]
+    fn lazy_ways_to_beat(&self, race : &Race) -> u128 {
+        (0..=race.time).filter(|&x| self.beats(x, race)).count() as u128
+    }
+}
+
+// binary searches on both sides of the best hold
+fn monotone_search<M : BoatModel + ?Sized>(model : &M, race : &Race) -> Option<RangeInclusive<u128>> {
+    let beats = |hold| model.beats(hold, race);
+    let best = model.best_hold(race.time);
+    if !beats(best) {
+        return None;
+    }
+    let (mut low, mut high) = (0, best);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if beats(mid) {high = mid} else {low = mid + 1}
+    };
+    let first = low;
+    let (mut low, mut high) = (best, race.time);
+    while low < high {
+        let mid = high - (high - low) / 2;
+        if beats(mid) {low = mid} else {high = mid - 1}
+    };
+    Some(first..=low)
+}
+
+// speed is the hold time
+struct Quadratic;
+
+// speed is `acceleration` times the hold time
+struct Accelerated {
+    acceleration : u128
+}
+
+// speed is the hold time, up to `max_speed`
+struct Capped {
+    max_speed : u128
+}
+
+// speed starts at the hold time, and drops by `decay` every millisecond after release
+struct Decaying {
+    decay : u128
+}
+
+impl BoatModel for Quadratic {
+    fn distance(&self, hold : u128, time : u128) -> Option<u128> {
+        hold.checked_mul(time - hold)
+    }
+
+    fn best_hold(&self, time : u128) -> u128 {
+        time / 2
+    }
+
+    fn winning_holds(&self, race : &Race) -> Option<RangeInclusive<u128>> {
+        race.shortest_win().map(|x| x..=race.time - x)
+    }
+}
+
+impl BoatModel for Accelerated {
+    fn distance(&self, hold : u128, time : u128) -> Option<u128> {
+        Quadratic.distance(hold, time)?.checked_mul(self.acceleration)
+    }
+
+    fn best_hold(&self, time : u128) -> u128 {
+        time / 2
+    }
+
+    // acceleration * d > distance exactly when d > distance / acceleration, rounded down
+    fn winning_holds(&self, race : &Race) -> Option<RangeInclusive<u128>> {
+        (self.acceleration != 0).then(|| Quadratic.winning_holds(&Race { time : race.time, distance : race.distance / self.acceleration }))?
     }
-    fn fast_ways_to_beat(&self) -> usize {
-        let a : f64 = -1 as f64;
-        let b : f64 = self.time as f64;
-        let c : f64 = -(self.distance as f64);
-        let delta = b * b - 4.0 * a * c;
-        if delta < 0.0 { return 0;}
-        let min = ((b - delta.sqrt()) / 2.0).ceil() as usize;
-        let max = ((b + delta.sqrt()) / 2.0).floor() as usize;
-        max + 1 - min
+}
+
+impl BoatModel for Capped {
+    fn distance(&self, hold : u128, time : u128) -> Option<u128> {
+        hold.min(self.max_speed).checked_mul(time - hold)
+    }
+
+    fn best_hold(&self, time : u128) -> u128 {
+        self.max_speed.min(time / 2)
+    }
+
+    // quadratic up to `max_speed`, then max_speed * (time - x) > distance gives the longest hold
+    fn winning_holds(&self, race : &Race) -> Option<RangeInclusive<u128>> {
+        let uncapped = Quadratic.winning_holds(race).filter(|holds| *holds.start() <= self.max_speed)?;
+        match *uncapped.end() <= self.max_speed {
+            true => Some(uncapped),
+            false => Some(*uncapped.start()..=race.time - race.distance / self.max_speed - 1)
+        }
+    }
+}
+
+impl BoatModel for Decaying {
+    // hold + (hold - decay) + ... + (hold - (moving - 1) * decay), as moving times the average of the first and last speeds :
+    // the last speed is positive, so only the final sum can overflow
+    fn distance(&self, hold : u128, time : u128) -> Option<u128> {
+        if self.decay == 0 {
+            return Quadratic.distance(hold, time);
+        }
+        let moving = (time - hold).min(hold.div_ceil(self.decay));
+        if moving == 0 {
+            return Some(0);
+        }
+        let last = hold - self.decay * (moving - 1);
+        match moving % 2 {
+            0 => hold.checked_add(last)?.checked_mul(moving / 2),
+            _ => (hold - self.decay * ((moving - 1) / 2)).checked_mul(moving)
+        }
+    }
+
+    // no closed form : holding a bit longer pays off until the peak, where distances too big to fit all count as one
+    fn best_hold(&self, time : u128) -> u128 {
+        let further = |a : Option<u128>, b : Option<u128>| match (a, b) {
+            (None, b) => b.is_some(),
+            (Some(a), Some(b)) => a > b,
+            (Some(_), None) => false
+        };
+        let (mut low, mut high) = (0, time);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if further(self.distance(mid + 1, time), self.distance(mid, time)) {low = mid + 1} else {high = mid}
+        };
+        low
     }
 }
 
+fn parse_model(name : &str) -> Option<Box<dyn BoatModel>> {
+    match name.split_once(':') {
+        None if name == "quadratic" => Some(Box::new(Quadratic)),
+        Some(("accelerated", n)) => Some(Box::new(Accelerated { acceleration : n.parse().ok()? })),
+        Some(("capped", n)) => Some(Box::new(Capped { max_speed : n.parse().ok()? })),
+        Some(("decaying", n)) => Some(Box::new(Decaying { decay : n.parse().ok()? })),
+        _ => None
+    }
+}
+
+#[derive(Debug)]
+enum RaceError {
+    MissingLine(&'static str),
+    NotANumber(&'static str, String),
+    MissingTime(usize),
+    MissingDistance(usize)
+}
+
+impl Display for RaceError {
+    fn fmt(&self, f : &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RaceError::MissingLine(name) => write!(f, "missing the \"{}:\" line", name),
+            RaceError::NotANumber(name, s) => write!(f, "\"{}\" on the \"{}:\" line is not a number", s, name),
+            RaceError::MissingTime(column) => write!(f, "column {} has a distance but no time", column),
+            RaceError::MissingDistance(column) => write!(f, "column {} has a time but no distance", column)
+        }
+    }
+}
+
+fn parse_line<'a>(line : Option<&'a str>, name : &'static str) -> Result<Vec<&'a str>, RaceError> {
+    let columns = line.and_then(|l| l.strip_prefix(name)).and_then(|l| l.strip_prefix(':'))
+        .ok_or(RaceError::MissingLine(name))?.split_ascii_whitespace().collect::<Vec<_>>();
+    match columns.iter().find(|s| s.parse::<u128>().is_err()) {
+        Some(s) => Err(RaceError::NotANumber(name, s.to_string())),
+        None => Ok(columns)
+    }
+}
 
+// the races, read column by column, and the single race read ignoring spaces
+fn parse_races(input : &str) -> Result<(Vec<Race>, Race), RaceError> {
+    let mut lines = input.lines().filter(|l| !l.trim().is_empty());
+    let times = parse_line(lines.next(), "Time")?;
+    let distances = parse_line(lines.next(), "Distance")?;
+    if times.len() < distances.len() {
+        return Err(RaceError::MissingTime(times.len() + 1));
+    }
+    if distances.len() < times.len() {
+        return Err(RaceError::MissingDistance(distances.len() + 1));
+    }
+    let races = times.iter().zip(distances.iter())
+        .map(|(t, d)| Race { time : t.parse().unwrap(), distance : d.parse().unwrap() }).collect();
+    let kerned = |columns : Vec<&str>, name| columns.concat().parse().map_err(|_| RaceError::NotANumber(name, columns.concat()));
+    Ok((races, Race { time : kerned(times, "Time")?, distance : kerned(distances, "Distance")? }))
+}
 
+// usage : day-6 [quadratic|accelerated:A|capped:S|decaying:D]
 fn main() {
-    let input = vec![
-        Race { time : 41, distance : 214},
-        Race { time : 96, distance : 1789},
-        Race { time : 88, distance : 1127},
-        Race { time : 94, distance : 1055}
-    ];
-    println!("part 1 : {}", input.iter().map(Race::fast_ways_to_beat).fold(1, |a,b| a*b));
-    println!("part 2 : {}", Race { time : 41968894, distance : 214178911271055}.fast_ways_to_beat());
+    let model = args().nth(1).map_or(Some(Box::new(Quadratic) as Box<dyn BoatModel>), |s| parse_model(&s))
+        .expect("unknown boat model, expected quadratic, accelerated:A, capped:S or decaying:D");
+    let (races, kerned) = match parse_races(&read_to_string("input.txt").unwrap_or("".to_string())) {
+        Ok(parsed) => parsed,
+        Err(e) => {eprintln!("{}", e); return}
+    };
+    println!("part 1 : {}", races.iter().map(|race| model.ways_to_beat(race)).product::<u128>());
+    println!("part 2 : {}", model.ways_to_beat(&kerned));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn models() -> Vec<Box<dyn BoatModel>> {
+        vec![
+            Box::new(Quadratic),
+            Box::new(Accelerated { acceleration : 3 }),
+            Box::new(Capped { max_speed : 7 }),
+            Box::new(Decaying { decay : 2 })
+        ]
+    }
 
+    #[test]
+    fn example() {
+        let (races, kerned) = parse_races("Time:      7  15   30\nDistance:  9  40  200\n").unwrap();
+        assert_eq!(races.iter().map(Race::fast_ways_to_beat).collect::<Vec<_>>(), vec![4, 8, 9]);
+        assert_eq!(kerned.fast_ways_to_beat(), 71503);
+    }
+
+    // holding 10 or 20 only ties the record
+    #[test]
+    fn ties_do_not_win() {
+        let race = Race { time : 30, distance : 200 };
+        assert_eq!(race.fast_ways_to_beat(), 9);
+        assert_eq!(race.lazy_ways_to_beat(), 9);
+        assert_eq!(race.shortest_win(), Some(11));
+    }
+
+    #[test]
+    fn zero_distance() {
+        assert_eq!(Race { time : 5, distance : 0 }.fast_ways_to_beat(), 4);
+        assert_eq!(Race { time : 1, distance : 0 }.fast_ways_to_beat(), 0);
+        assert_eq!(Race { time : 0, distance : 0 }.fast_ways_to_beat(), 0);
+    }
+
+    #[test]
+    fn no_win() {
+        assert_eq!(Race { time : 6, distance : 9 }.fast_ways_to_beat(), 0);
+        assert_eq!(Race { time : 5, distance : 100 }.fast_ways_to_beat(), 0);
+        assert!(models().iter().all(|m| m.ways_to_beat(&Race { time : 5, distance : 1000 }) == 0));
+    }
+
+    // 2^70 and 2^65 milliseconds : far too long to try every hold, and some distances do not fit in a u128
+    #[test]
+    fn large_races_for_every_model() {
+        let long = Race { time : 1 << 70, distance : 5 };
+        let record = Race { time : 1 << 65, distance : u128::MAX };
+        let mut models = models();
+        models.push(Box::new(Decaying { decay : 1 }));
+        let expected = [
+            ((1 << 70) - 1, 1),
+            ((1 << 70) - 1, 30123406930865283009),
+            ((1 << 70) - 1, 0),
+            ((1 << 70) - 4, 0),
+            ((1 << 70) - 3, 0)
+        ];
+        for (model, (long_ways, record_ways)) in models.iter().zip(expected) {
+            assert_eq!(model.ways_to_beat(&long), long_ways);
+            assert_eq!(model.ways_to_beat(&record), record_ways);
+        }
+    }
+
+    // far above 2^53, where floating point roots go wrong
+    #[test]
+    fn large_races() {
+        assert_eq!(Race { time : 1 << 64, distance : (1 << 126) - 4 }.fast_ways_to_beat(), 3);
+        assert_eq!(Race { time : 1 << 64, distance : (1 << 126) - 1 }.fast_ways_to_beat(), 1);
+        // time² does not fit in u128
+        assert_eq!(Race { time : 1 << 100, distance : u128::MAX }.fast_ways_to_beat(), 1267650600228229401496166334463);
+    }
+
+    #[test]
+    fn fast_agrees_with_brute_force() {
+        for time in 0..40 {
+            for distance in 0..time * time / 4 + 2 {
+                let race = Race { time, distance };
+                assert_eq!(race.fast_ways_to_beat(), race.lazy_ways_to_beat(), "time {} distance {}", time, distance);
+            }
+        }
+    }
+
+    #[test]
+    fn models_agree_with_brute_force() {
+        for model in models() {
+            for time in 0..40 {
+                for distance in (0..3 * time * time).step_by(7) {
+                    let race = Race { time, distance };
+                    assert_eq!(model.ways_to_beat(&race), model.lazy_ways_to_beat(&race), "time {} distance {}", time, distance);
+                }
+            }
+        }
+    }
 }
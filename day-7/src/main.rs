@@ -1,4 +1,5 @@
-use std::{fs::read_to_string, iter::Peekable, cmp::Ordering};
+use std::{env::args, fmt::{self, Display, Formatter}, fs::read_to_string, iter::successors};
+use iter_ext::IterExt;
 
 
 #[derive(Debug,Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -24,142 +25,291 @@ impl Card {
             'Q' => Some(Self::Head(Head::Queen)),
             'J' => Some(Self::Head(Head::Jester)),
             'T' => Some(Self::Number(10)),
-            c => c.to_digit(10).map(|n| Self::Number(n as u8))
+            c => c.to_digit(10).filter(|n| *n >= 2).map(|n| Self::Number(n as u8))
+        }
+    }
+
+    fn char(&self) -> char {
+        match self {
+            Self::Head(Head::Ace) => 'A',
+            Self::Head(Head::King) => 'K',
+            Self::Head(Head::Queen) => 'Q',
+            Self::Head(Head::Jester) => 'J',
+            Self::Number(10) => 'T',
+            Self::Number(n) => char::from_digit(*n as u32, 10).unwrap_or('?')
         }
     }
 }
 
-struct GroupCounter<I : Iterator> 
-where
-    I::Item : PartialEq
-{
-    iter : Peekable<I>
+
+#[derive(Debug,Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind
 }
 
-impl<I : Iterator> Iterator for GroupCounter<I>
-where
-    I::Item : PartialEq
-{
-    type Item = (usize, I::Item);
-
-    fn next(&mut self) -> Option<Self::Item> {
-
-        match self.iter.next() {
-            Some(x) => {
-                let mut count : usize = 1;
-                while self.iter.peek() == Some(&x) {
-                    count += 1;
-                    self.iter.next();
-                }
-                Some((count, x))
-            }
-            None => None
+impl HandType {
+    // `counts` are the sizes of the groups of identical cards, largest first
+    fn from_counts(counts : &[usize]) -> Self {
+        match (counts.first().copied().unwrap_or(0), counts.get(1).copied().unwrap_or(0)) {
+            (5.., _) => Self::FiveOfAKind,
+            (4, _) => Self::FourOfAKind,
+            (3, 2..) => Self::FullHouse,
+            (3, _) => Self::ThreeOfAKind,
+            (2, 2..) => Self::TwoPair,
+            (2, _) => Self::OnePair,
+            _ => Self::HighCard
         }
     }
 }
 
-trait GroupCounts<T>
-where
-    Self : Iterator<Item = T> + Sized,
-    T : PartialEq 
-{
-    fn counts(self) -> GroupCounter<Self>;
+#[derive(Debug,Clone, PartialEq, Eq)]
+struct Hand {
+    cards : Vec<Card>
 }
 
-impl<I, T> GroupCounts<T> for I
-where
-    I  : Iterator<Item = T> + Sized,
-    T : PartialEq 
-{
-    fn counts(self) -> GroupCounter<Self> {
-        GroupCounter { iter: self.peekable() }
+impl Hand {
+    // None if any of the characters is not a card
+    fn parse(s : &str) -> Option<Hand> {
+        let cards = s.chars().map(Card::parse).collect::<Option<Vec<_>>>()?;
+        (!cards.is_empty()).then_some(Hand { cards })
     }
-}
 
+    // wildcards all join the largest group
+    fn hand_type(&self, rules : &Rules) -> HandType {
+        let mut c = self.cards.clone();
+        c.sort();
+        let wild_count = c.iter().filter(|c| rules.is_wild(c)).count();
+        let mut counts = c.into_iter().filter(|c| !rules.is_wild(c)).counts().map(|(n, _)| n).collect::<Vec<_>>();
+        counts.sort();
+        counts.reverse();
+        match counts.first_mut() {
+            Some(n) => *n += wild_count,
+            None => counts.push(wild_count)
+        };
+        HandType::from_counts(&counts)
+    }
+}
 
-#[derive(Debug,Clone, Copy, PartialEq, Eq)]
-struct Figure {
-    mainCard : Card,
-    mainNumber : u8,
-    additionalPair : Option<Card>
+struct Rules {
+    // cards standing in for whichever card makes the best figure
+    wildcards : Vec<Card>,
+    // cards from weakest to strongest, used to break ties between figures
+    order : Vec<Card>
 }
 
+impl Rules {
+    fn classic() -> Self {
+        Self {
+            wildcards : vec![],
+            order : (2..=10).map(Card::Number).chain([Head::Jester, Head::Queen, Head::King, Head::Ace].map(Card::Head)).collect()
+        }
+    }
+
+    fn jokers() -> Self {
+        Self {
+            wildcards : vec![Card::Head(Head::Jester)],
+            order : [Card::Head(Head::Jester)].into_iter().chain((2..=10).map(Card::Number))
+                .chain([Head::Queen, Head::King, Head::Ace].map(Card::Head)).collect()
+        }
+    }
+
+    fn is_wild(&self, card : &Card) -> bool {
+        self.wildcards.contains(card)
+    }
+
+    fn strength(&self, card : &Card) -> usize {
+        self.order.iter().position(|c| c == card).unwrap_or(0)
+    }
+
+    // hand type first, then the cards one by one
+    fn key(&self, hand : &Hand) -> (HandType, Vec<usize>) {
+        (hand.hand_type(self), hand.cards.iter().map(|c| self.strength(c)).collect())
+    }
+
+    // weakest hand first
+    fn rank(&self, hands : &[(Hand, usize)]) -> Vec<(Hand, usize)> {
+        let mut ranked = hands.to_vec();
+        ranked.sort_by_cached_key(|(h, _)| self.key(h));
+        ranked
+    }
+
+    fn winnings(&self, hands : &[(Hand, usize)]) -> usize {
+        self.rank(hands).iter().zip(1_usize..).map(|((_, u), n )| u * n).sum()
+    }
 
-impl PartialOrd for Figure {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(
-            match (self.mainNumber.partial_cmp(&other.mainNumber)?, self.additionalPair, other.additionalPair) {
-                (Ordering::Equal, Some(_), None) => Ordering::Greater,
-                (Ordering::Equal, None, Some(_)) => Ordering::Less,
-                (ord, _, _) => ord,
-            }
-        )
+    fn report(&self, hands : &[(Hand, usize)]) {
+        for ((hand, bid), rank) in self.rank(hands).iter().zip(1_usize..) {
+            println!("{:>5} {} {:<12} {}", rank, hand.cards.iter().map(Card::char).collect::<String>(), format!("{:?}", hand.hand_type(self)), bid);
+        }
     }
 }
 
-impl Ord for Figure {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self.mainNumber.cmp(&other.mainNumber), self.additionalPair, other.additionalPair) {
-            (Ordering::Equal, Some(_), None) => Ordering::Greater,
-            (Ordering::Equal, None, Some(_)) => Ordering::Less,
-            (ord, _, _) => ord,
+#[derive(Debug,Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades
+}
+
+impl Suit {
+    fn parse(c : char) -> Option<Self> {
+        match c {
+            'C' => Some(Self::Clubs),
+            'D' => Some(Self::Diamonds),
+            'H' => Some(Self::Hearts),
+            'S' => Some(Self::Spades),
+            _ => None
+        }
+    }
+
+    fn char(&self) -> char {
+        match self {
+            Self::Clubs => 'C',
+            Self::Diamonds => 'D',
+            Self::Hearts => 'H',
+            Self::Spades => 'S'
         }
     }
 }
 
 #[derive(Debug,Clone, Copy, PartialEq, Eq)]
-struct Hand {
-    cards : [Card;5],
-    figure : Option<Figure>,
-}
-
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(
-            match (self.figure, other.figure, self.cards.cmp(&other.cards)) {
-                (Some(_), None, _) => Ordering::Greater,
-                (None, Some(_), _) => Ordering::Less,
-                (Some(a), Some(b), _) if  a.cmp(&b) != Ordering::Equal => a.cmp(&b),
-                (_,_,x) => x
-            }
-        )
-    }
-}
-
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self.figure, other.figure, self.cards.cmp(&other.cards)) {
-            (Some(_), None, _) => Ordering::Greater,
-            (None, Some(_), _) => Ordering::Less,
-            (Some(a), Some(b), _) if  a.cmp(&b) != Ordering::Equal => a.cmp(&b),
-            (_,_,x) => x
+struct SuitedCard {
+    card : Card,
+    suit : Suit
+}
+
+impl SuitedCard {
+    // rank then suit, as in "TH" or "2C"
+    fn parse(s : &str) -> Option<Self> {
+        let mut chars = s.chars();
+        let card = Card::parse(chars.next()?)?;
+        let suit = Suit::parse(chars.next()?)?;
+        chars.next().is_none().then_some(Self { card, suit })
+    }
+}
+
+#[derive(Debug,Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PokerHand {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+    FiveOfAKind
+}
+
+impl From<HandType> for PokerHand {
+    fn from(hand_type : HandType) -> Self {
+        match hand_type {
+            HandType::HighCard => Self::HighCard,
+            HandType::OnePair => Self::OnePair,
+            HandType::TwoPair => Self::TwoPair,
+            HandType::ThreeOfAKind => Self::ThreeOfAKind,
+            HandType::FullHouse => Self::FullHouse,
+            HandType::FourOfAKind => Self::FourOfAKind,
+            HandType::FiveOfAKind => Self::FiveOfAKind
         }
     }
 }
 
-impl Hand {
-    fn parse<I>(mut cards : I) -> Option<Hand>
-    where
-        I : Iterator<Item=Card>
-    {
-        let mut arr : [Card;5] = [Card::Number(0);5]; 
-        for i in 0..5 as usize {
-            arr[i] = cards.next()?
-        };
-        let mut c = arr.clone();
-        c.sort();
-        let mut counts = c.into_iter().counts().collect::<Vec<_>>();
-        counts.sort_by_key(|(n,_)| *n);
-        counts.reverse();
-        Some( Hand { cards : arr, 
-            figure : match counts.get(0) {
-                None | Some((1, _)) => None,
-                Some((n, c)) => Some(Figure { mainCard: *c, mainNumber: *n as u8, 
-                    additionalPair: counts.get(1).filter(|(n, _)| *n > 1).map(|(_, c)| *c)
-                    })
-                }
-            }
-        )
+// the hand, then its kickers : card strengths by group size, then strength
+fn poker_five(cards : &[SuitedCard]) -> (PokerHand, Vec<usize>) {
+    let rules = Rules::classic();
+    let mut values = cards.iter().map(|c| rules.strength(&c.card)).collect::<Vec<_>>();
+    values.sort();
+    values.reverse();
+    let mut groups = values.into_iter().counts().collect::<Vec<_>>();
+    groups.sort();
+    groups.reverse();
+    let counts = groups.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+    let kickers = groups.iter().map(|(_, v)| *v).collect::<Vec<_>>();
+    let flush = cards.len() == 5 && cards.iter().all(|c| c.suit == cards[0].suit);
+    // an ace can also go below the 2, making 5 the highest card
+    let straight = match kickers[..] {
+        [high, _, _, _, low] if high - low == 4 => Some(high),
+        [12, 3, 2, 1, 0] => Some(3),
+        _ => None
+    };
+    match (straight, flush, HandType::from_counts(&counts)) {
+        (_, _, HandType::FiveOfAKind) => (PokerHand::FiveOfAKind, kickers),
+        (Some(high), true, _) => (PokerHand::StraightFlush, vec![high]),
+        (_, _, hand_type @ (HandType::FourOfAKind | HandType::FullHouse)) => (hand_type.into(), kickers),
+        (_, true, _) => (PokerHand::Flush, kickers),
+        (Some(high), _, _) => (PokerHand::Straight, vec![high]),
+        (_, _, hand_type) => (hand_type.into(), kickers)
+    }
+}
+
+// the indices of the next five cards out of `n`, in lexicographic order
+fn next_five(mut indices : [usize; 5], n : usize) -> Option<[usize; 5]> {
+    let i = (0..5).rev().find(|&i| indices[i] < n - 5 + i)?;
+    indices[i] += 1;
+    for j in i + 1..5 {
+        indices[j] = indices[j - 1] + 1;
+    };
+    Some(indices)
+}
+
+// hands of more than five cards play their best five
+fn poker_key(cards : &[SuitedCard]) -> (PokerHand, Vec<usize>) {
+    if cards.len() <= 5 {
+        return poker_five(cards);
+    }
+    successors(Some([0, 1, 2, 3, 4]), |indices| next_five(*indices, cards.len()))
+        .map(|indices| poker_five(&indices.map(|i| cards[i])))
+        .max().unwrap()
+}
+
+#[derive(Debug)]
+enum PokerError {
+    BadCard(String),
+    Duplicate(String),
+    Empty
+}
+
+impl Display for PokerError {
+    fn fmt(&self, f : &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PokerError::BadCard(s) => write!(f, "\"{}\" is not a card", s),
+            PokerError::Duplicate(s) => write!(f, "{} appears more than once", s),
+            PokerError::Empty => write!(f, "no cards")
+        }
+    }
+}
+
+// cards separated by spaces, each at most once
+fn parse_poker_hand(line : &str) -> Result<Vec<SuitedCard>, PokerError> {
+    let mut cards = vec![];
+    for s in line.split_ascii_whitespace() {
+        let card = SuitedCard::parse(s).ok_or_else(|| PokerError::BadCard(s.to_string()))?;
+        if cards.contains(&card) {
+            return Err(PokerError::Duplicate(s.to_string()));
+        }
+        cards.push(card);
+    };
+    (!cards.is_empty()).then_some(cards).ok_or(PokerError::Empty)
+}
+
+// one hand per line, weakest hand first
+fn poker_report(input : &str) {
+    let mut hands = input.lines().enumerate().filter(|(_, l)| !l.trim().is_empty())
+        .filter_map(|(n, l)| parse_poker_hand(l).map_err(|e| eprintln!("line {} skipped : {}", n + 1, e)).ok())
+        .map(|cards| (poker_key(&cards), cards)).collect::<Vec<_>>();
+    hands.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (((hand, _), cards), rank) in hands.iter().zip(1_usize..) {
+        let cards = cards.iter().map(|c| format!("{}{}", c.card.char(), c.suit.char())).collect::<Vec<_>>().join(" ");
+        println!("{:>5} {} {:?}", rank, cards, hand);
     }
 }
 
@@ -167,13 +317,90 @@ fn parse_line(line : &str) -> Option<(Hand, usize)>
 {
     let mut split = line.split_ascii_whitespace();
     Some((
-        Hand::parse(split.next()?.chars().filter_map(Card::parse))?,
+        Hand::parse(split.next()?)?,
         split.next()?.parse::<usize>().ok()?
     ))
 }
 
+// usage : day-7 [--report] [--poker PATH]
 fn main() {
-    let mut res = read_to_string("input.txt").unwrap_or("".to_string()).lines().filter_map(parse_line).collect::<Vec<_>>();
-    res.sort_by_key(|(h, _)| *h);
-    println!("part 1 : {}", res.iter().zip(1 as usize..).map(|((_, u), n )| u * n).sum::<usize>());
+    if let Some(path) = args().skip_while(|arg| arg != "--poker").nth(1) {
+        poker_report(&read_to_string(path).unwrap_or("".to_string()));
+        return;
+    }
+    let hands = read_to_string("input.txt").unwrap_or("".to_string()).lines().enumerate().filter(|(_, l)| !l.trim().is_empty())
+        .filter_map(|(n, l)| parse_line(l).or_else(|| {eprintln!("line {} skipped : \"{}\" is not a hand and a bid", n + 1, l); None})).collect::<Vec<_>>();
+    if args().any(|arg| arg == "--report") {
+        println!("part 1 :");
+        Rules::classic().report(&hands);
+        println!("part 2 :");
+        Rules::jokers().report(&hands);
+    }
+    println!("part 1 : {}", Rules::classic().winnings(&hands));
+    println!("part 2 : {}", Rules::jokers().winnings(&hands));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poker(line : &str) -> (PokerHand, Vec<usize>) {
+        poker_key(&parse_poker_hand(line).unwrap())
+    }
+
+    #[test]
+    fn camel_cards_example() {
+        let hands = ["32T3K 765", "T55J5 684", "KK677 28", "KTJJT 220", "QQQJA 483"].into_iter().filter_map(parse_line).collect::<Vec<_>>();
+        assert_eq!(Rules::classic().winnings(&hands), 6440);
+        assert_eq!(Rules::jokers().winnings(&hands), 5905);
+    }
+
+    #[test]
+    fn malformed_hands_are_rejected() {
+        assert!(parse_line("32X3K 765").is_none());
+        assert!(parse_line("32T3K").is_none());
+        assert!(parse_line(" 765").is_none());
+        assert!(parse_line("3213K 765").is_none());
+        assert_eq!(parse_line("32T3K 765").map(|(h, bid)| (h.cards.len(), bid)), Some((5, 765)));
+    }
+
+    #[test]
+    fn ace_low_straight() {
+        assert_eq!(poker("AH 2D 3C 4H 5S"), (PokerHand::Straight, vec![3]));
+        assert!(poker("AH 2D 3C 4H 5S") < poker("2D 3C 4H 5S 6H"));
+        assert!(poker("TH JD QC KH AS") > poker("9D TC JH QS KH"));
+        // no wrapping around the ace
+        assert_eq!(poker("QH KD AC 2H 3S").0, PokerHand::HighCard);
+    }
+
+    #[test]
+    fn straight_flush() {
+        assert_eq!(poker("5H 6H 7H 8H 9H"), (PokerHand::StraightFlush, vec![7]));
+        assert_eq!(poker("AD 2D 3D 4D 5D"), (PokerHand::StraightFlush, vec![3]));
+        assert!(poker("AD 2D 3D 4D 5D") > poker("KS KH KD KC AS"));
+        assert_eq!(poker("2H 4H 6H 8H TH").0, PokerHand::Flush);
+    }
+
+    #[test]
+    fn kickers_break_ties() {
+        assert!(poker("KH KD AC 4H 2S") > poker("KS KC QC JH 9S"));
+        assert!(poker("KH KD 7C 7H 2S") > poker("KS KC 6D 6S AS"));
+        assert!(poker("8H 8D 8C 2H 2S") < poker("9H 9D 9C 2D 2C"));
+        assert!(poker("AH QD 9C 5H 3S") > poker("AD QC 9H 5S 2S"));
+        assert_eq!(poker("AH QD 9C 5H 3S"), poker("AD QC 9H 5S 3C"));
+    }
+
+    #[test]
+    fn best_five_of_seven() {
+        assert_eq!(poker("2H 3H 4H 5H 9H 9D 9C"), (PokerHand::Flush, vec![7, 3, 2, 1, 0]));
+        assert_eq!(poker("AH KD 2C 3H 4S 5D 9C"), (PokerHand::Straight, vec![3]));
+    }
+
+    #[test]
+    fn bad_hands_are_rejected() {
+        assert!(matches!(parse_poker_hand("1H 2D 3C 4H 5S"), Err(PokerError::BadCard(_))));
+        assert!(matches!(parse_poker_hand("0H 2D 3C 4H 5S"), Err(PokerError::BadCard(_))));
+        assert!(matches!(parse_poker_hand("2H 2D 3C 2H 5S"), Err(PokerError::Duplicate(_))));
+        assert!(matches!(parse_poker_hand(""), Err(PokerError::Empty)));
+    }
 }
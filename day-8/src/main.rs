@@ -1,7 +1,7 @@
-use std::{fs::read_to_string, collections::HashMap};
+use std::{env::args, fs::{read_to_string, write}, collections::HashMap, fmt::{self, Display, Formatter}};
 
 use enum_map::{Enum, EnumMap, enum_map};
-use num::integer::lcm;
+use num::Integer;
 use regex::Regex;
 
 
@@ -22,14 +22,176 @@ impl Direction {
     }
 }
 
-fn parse_line(line : &str) -> Option<(String, EnumMap<Direction, String>)> {
-    let capt = Regex::new("([A-Z]+) = \\(([A-Z]+), ([A-Z]+)\\)").unwrap().captures(line)?;
-    Some((capt.get(1)?.as_str().to_owned(), enum_map! {
-        Direction::Left => capt.get(2)?.as_str().to_owned(),
-        Direction::Right => capt.get(3)?.as_str().to_owned()
+fn parse_line<'a>(regex : &Regex, line : &'a str) -> Option<(&'a str, EnumMap<Direction, &'a str>)> {
+    let capt = regex.captures(line)?;
+    Some((capt.get(1)?.as_str(), enum_map! {
+        Direction::Left => capt.get(2)?.as_str(),
+        Direction::Right => capt.get(3)?.as_str()
     }))
 }
 
+// nodes are numbered in order of appearance, edges are stored by number
+struct Network {
+    labels : Vec<String>,
+    ids : HashMap<String, usize>,
+    next : Vec<EnumMap<Direction, usize>>
+}
+
+impl Network {
+    fn parse<'a, I>(lines : I) -> Self
+    where
+        I : Iterator<Item = &'a str>
+    {
+        let regex = Regex::new("([0-9A-Za-z]+) = \\(([0-9A-Za-z]+), ([0-9A-Za-z]+)\\)").unwrap();
+        let mut network = Self { labels : vec![], ids : HashMap::new(), next : vec![] };
+        for (node, targets) in lines.filter_map(|line| parse_line(&regex, line)) {
+            let id = network.intern(node);
+            network.next[id] = targets.map(|_, target| network.intern(target));
+        };
+        network
+    }
+
+    // a node only seen as a target loops onto itself until its own line is read
+    fn intern(&mut self, label : &str) -> usize {
+        match self.ids.get(label) {
+            Some(&id) => id,
+            None => {
+                let id = self.labels.len();
+                self.labels.push(label.to_owned());
+                self.ids.insert(label.to_owned(), id);
+                self.next.push(enum_map! { _ => id });
+                id
+            }
+        }
+    }
+
+    fn id(&self, label : &str) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    fn label(&self, id : usize) -> &str {
+        &self.labels[id]
+    }
+
+    fn step(&self, id : usize, direction : Direction) -> usize {
+        self.next[id][direction]
+    }
+
+    fn ends_with(&self, c : char) -> Vec<bool> {
+        self.labels.iter().map(|label| label.ends_with(c)).collect()
+    }
+
+    // starts in green, ends in red, one edge per direction
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph network {\n");
+        for (id, label) in self.labels.iter().enumerate() {
+            match label.chars().last() {
+                Some('A') => dot.push_str(&format!("    \"{}\" [style=filled, fillcolor=palegreen];\n", label)),
+                Some('Z') => dot.push_str(&format!("    \"{}\" [style=filled, fillcolor=salmon];\n", label)),
+                _ => ()
+            };
+            let (left, right) = (self.step(id, Direction::Left), self.step(id, Direction::Right));
+            if left == right {
+                dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"LR\"];\n", label, self.label(left)));
+            } else {
+                dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"L\"];\n", label, self.label(left)));
+                dot.push_str(&format!("    \"{}\" -> \"{}\" [label=\"R\"];\n", label, self.label(right)));
+            }
+        };
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn reachable(&self, start : usize) -> Vec<bool> {
+        let mut seen = vec![false; self.labels.len()];
+        let mut todo = vec![start];
+        seen[start] = true;
+        while let Some(id) = todo.pop() {
+            for (_, &next) in self.next[id].iter() {
+                if !seen[next] {
+                    seen[next] = true;
+                    todo.push(next);
+                }
+            }
+        };
+        seen
+    }
+
+    // strongly connected component of each node (Kosaraju, without recursion)
+    fn components(&self) -> Vec<usize> {
+        let mut order = vec![];
+        let mut seen = vec![false; self.labels.len()];
+        for root in 0..self.labels.len() {
+            if seen[root] {
+                continue;
+            }
+            seen[root] = true;
+            let mut stack = vec![(root, 0)];
+            while let Some((id, child)) = stack.pop() {
+                match [Direction::Left, Direction::Right].get(child) {
+                    Some(&direction) => {
+                        stack.push((id, child + 1));
+                        let next = self.step(id, direction);
+                        if !seen[next] {
+                            seen[next] = true;
+                            stack.push((next, 0));
+                        }
+                    },
+                    None => order.push(id)
+                }
+            }
+        };
+        let mut previous = vec![vec![]; self.labels.len()];
+        for (id, next) in self.next.iter().enumerate() {
+            for (_, &n) in next.iter() {
+                previous[n].push(id);
+            }
+        };
+        let mut component = vec![usize::MAX; self.labels.len()];
+        let mut count = 0;
+        for &root in order.iter().rev() {
+            if component[root] != usize::MAX {
+                continue;
+            }
+            component[root] = count;
+            let mut todo = vec![root];
+            while let Some(id) = todo.pop() {
+                for &p in &previous[id] {
+                    if component[p] == usize::MAX {
+                        component[p] = count;
+                        todo.push(p);
+                    }
+                }
+            };
+            count += 1;
+        };
+        component
+    }
+
+    fn analyse(&self) {
+        let components = self.components();
+        let mut sizes = vec![0; components.iter().map(|&c| c + 1).max().unwrap_or(0)];
+        components.iter().for_each(|&c| sizes[c] += 1);
+        println!("{} nodes, {} strongly connected components", self.labels.len(), sizes.len());
+        for (c, size) in sizes.iter().enumerate().filter(|(_, &size)| size > 1) {
+            let mut special = (0..self.labels.len()).filter(|&id| components[id] == c)
+                .map(|id| self.label(id)).filter(|l| l.ends_with('A') || l.ends_with('Z')).collect::<Vec<_>>();
+            special.sort();
+            println!("component of {} nodes, containing {}", size, match special.is_empty() {
+                true => "no start or end".to_owned(),
+                false => special.join(", ")
+            });
+        };
+        let (starts, ends) = (self.ends_with('A'), self.ends_with('Z'));
+        for start in (0..self.labels.len()).filter(|&id| starts[id]) {
+            let reachable = self.reachable(start);
+            let mut reached = (0..self.labels.len()).filter(|&id| reachable[id] && ends[id]).map(|id| self.label(id)).collect::<Vec<_>>();
+            reached.sort();
+            println!("{} reaches {} nodes, ends : {}", self.label(start), reachable.iter().filter(|&&r| r).count(), reached.join(", "));
+        }
+    }
+}
+
 struct Loop<T> {
     vec : Vec<T>,
     index : usize
@@ -69,31 +231,257 @@ where
 }
 
 
+// a ghost's walk : `tail` steps before entering a `cycle` of states (node, instruction index),
+// and every step before the first repeated state where it stands on an end node
+struct Ghost {
+    start : usize,
+    tail : usize,
+    cycle : usize,
+    hits : Vec<usize>
+}
+
+impl Ghost {
+    fn analyse(network : &Network, directions : &[Direction], ends : &[bool], start : usize) -> Self {
+        let mut first_visit = vec![usize::MAX; network.labels.len() * directions.len()];
+        let (mut node, mut step, mut hits) = (start, 0, vec![]);
+        loop {
+            let state = node * directions.len() + step % directions.len();
+            if first_visit[state] != usize::MAX {
+                return Self { start, tail : first_visit[state], cycle : step - first_visit[state], hits };
+            }
+            first_visit[state] = step;
+            if ends[node] {
+                hits.push(step);
+            }
+            (node, step) = (network.step(node, directions[step % directions.len()]), step + 1);
+        }
+    }
+
+    fn hits_at(&self, step : usize) -> bool {
+        let step = match step < self.tail {
+            true => step,
+            false => self.tail + (step - self.tail) % self.cycle
+        };
+        self.hits.binary_search(&step).is_ok()
+    }
+
+    // (remainder, modulus) pairs of the end steps repeating forever
+    fn residues(&self) -> impl Iterator<Item = (i128, i128)> + '_ {
+        self.hits.iter().filter(|&&h| h >= self.tail).map(|&h| ((h % self.cycle) as i128, self.cycle as i128))
+    }
+}
+
+// the steps congruent to both (a, m) and (b, n), if any
+fn crt((a, m) : (i128, i128), (b, n) : (i128, i128)) -> Option<(i128, i128)> {
+    let gcd = m.extended_gcd(&n);
+    ((b - a) % gcd.gcd == 0).then(|| {
+        let lcm = m / gcd.gcd * n;
+        ((a + (b - a) / gcd.gcd * gcd.x % (n / gcd.gcd) * m).rem_euclid(lcm), lcm)
+    })
+}
+
+#[derive(Debug)]
+enum WalkError {
+    NoStart,
+    NeverEnds(String),
+    NeverTogether
+}
+
+impl Display for WalkError {
+    fn fmt(&self, f : &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WalkError::NoStart => write!(f, "no node ends with A"),
+            WalkError::NeverEnds(label) => write!(f, "the ghost starting at {} never reaches a node ending with Z", label),
+            WalkError::NeverTogether => write!(f, "the ghosts never all stand on a node ending with Z at the same time")
+        }
+    }
+}
+
+fn synchronise(network : &Network, ghosts : &[Ghost]) -> Result<usize, WalkError> {
+    if ghosts.is_empty() {
+        return Err(WalkError::NoStart);
+    }
+    if let Some(ghost) = ghosts.iter().find(|g| g.hits.is_empty()) {
+        return Err(WalkError::NeverEnds(network.label(ghost.start).to_owned()));
+    }
+    let all_cycling = ghosts.iter().map(|g| g.tail).max().unwrap_or(0);
+    if let Some(step) = (0..all_cycling).find(|&step| ghosts.iter().all(|g| g.hits_at(step))) {
+        return Ok(step);
+    }
+    let mut solutions = vec![(0, 1)];
+    for ghost in ghosts {
+        solutions = solutions.iter().flat_map(|&s| ghost.residues().filter_map(move |r| crt(s, r))).collect();
+        solutions.sort();
+        solutions.dedup();
+    };
+    // first step of each solution once every ghost is in its cycle
+    let all_cycling = all_cycling as i128;
+    solutions.into_iter().map(|(r, m)| r + ((all_cycling - r).max(0) + m - 1) / m * m).min()
+        .map(|step| step as usize).ok_or(WalkError::NeverTogether)
+}
+
+// usage : day-8 [--dot PATH] [--analyse]
 fn main() {
     let input = read_to_string("input.txt").unwrap_or("".to_string());
     let mut lines =input.lines();
     let mut directions = Loop::new(lines.next().unwrap().chars().filter_map(Direction::parse).collect::<Vec<_>>());
     let direction_nb = directions.len();
     println!("loop_length : {direction_nb}");
-    let map = lines.filter_map(parse_line).collect::<HashMap<String, EnumMap<Direction, String>>>();
-    println!("part 1 : {}", {
-        let (mut node, mut steps) = ("AAA".to_owned(), 0);
-        while node != "ZZZ".to_owned() {
-            (node, steps) = (map[&node][directions.next().unwrap()].clone(), steps + 1)
-        };
-        steps
-    });
-    let loops = map.keys().filter(|s| s.chars().nth(2).unwrap() == 'A').map(String::clone).
-        map(|mut node|{
-            let (mut start, mut loop_len, mut direction) : (usize, usize, Loop<Direction>) = (0, 0, directions.clone());
-            while node.chars().nth(2) != Some('Z') {
-                (node, start) = (map[&node][direction.next().unwrap()].clone(), start + 1)
-            };
-            (node, loop_len) = (map[&node][direction.next().unwrap()].clone(), 1);
-            while node.chars().nth(2) != Some('Z') || loop_len % direction_nb != 0 {
-                (node, loop_len) = (map[&node][direction.next().unwrap()].clone(), loop_len + 1)
+    let network = Network::parse(lines);
+    if let Some(path) = args().skip_while(|arg| arg != "--dot").nth(1) {
+        if let Err(e) = write(&path, network.to_dot()) {
+            eprintln!("could not write {} : {}", path, e);
+        }
+    }
+    if args().any(|arg| arg == "--analyse") {
+        network.analyse();
+    }
+    if let (Some(start), Some(end)) = (network.id("AAA"), network.id("ZZZ")) {
+        println!("part 1 : {}", {
+            let (mut node, mut steps) = (start, 0);
+            while node != end {
+                (node, steps) = (network.step(node, directions.next().unwrap()), steps + 1)
             };
-            loop_len
-    }).collect::<Vec<_>>();
-    println!("part 2 : {}", loops.iter().fold(1, |n, m| lcm(n,*m)));
+            steps
+        });
+    }
+    let (starts, ends) = (network.ends_with('A'), network.ends_with('Z'));
+    let ghosts = (0..starts.len()).filter(|&id| starts[id])
+        .map(|start| Ghost::analyse(&network, &directions.vec, &ends, start)).collect::<Vec<_>>();
+    match synchronise(&network, &ghosts) {
+        Ok(steps) => println!("part 2 : {}", steps),
+        Err(e) => eprintln!("{}", e)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the example of part 2
+    const GHOSTS : &str = concat!(
+        "11A = (11B, XXX)\n",
+        "11B = (XXX, 11Z)\n",
+        "11Z = (11B, XXX)\n",
+        "22A = (22B, XXX)\n",
+        "22B = (22C, 22C)\n",
+        "22C = (22Z, 22Z)\n",
+        "22Z = (22B, 22B)\n",
+        "XXX = (XXX, XXX)\n"
+    );
+
+    fn ghosts(network : &Network, directions : &str) -> Vec<Ghost> {
+        let directions = directions.chars().filter_map(Direction::parse).collect::<Vec<_>>();
+        let (starts, ends) = (network.ends_with('A'), network.ends_with('Z'));
+        (0..starts.len()).filter(|&id| starts[id]).map(|start| Ghost::analyse(network, &directions, &ends, start)).collect()
+    }
+
+    fn walk(input : &str, directions : &str) -> Result<usize, WalkError> {
+        let network = Network::parse(input.lines());
+        synchronise(&network, &ghosts(&network, directions))
+    }
+
+    #[test]
+    fn generalised_crt() {
+        assert_eq!(crt((2, 3), (3, 5)), Some((8, 15)));
+        // moduli sharing a factor
+        assert_eq!(crt((1, 4), (3, 6)), Some((9, 12)));
+        assert_eq!(crt((1, 4), (2, 6)), None);
+        assert_eq!(crt((0, 1), (4, 7)), Some((4, 7)));
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(walk(GHOSTS, "LR").unwrap(), 6);
+    }
+
+    #[test]
+    fn tail_and_cycle() {
+        let network = Network::parse(GHOSTS.lines());
+        let ghosts = ghosts(&network, "LR");
+        // 11A, then 11B 11Z over and over : the state (11B, R) comes back after two steps
+        assert_eq!((ghosts[0].tail, ghosts[0].cycle, ghosts[0].hits.clone()), (1, 2, vec![2]));
+        assert!(ghosts[0].hits_at(2) && ghosts[0].hits_at(10) && !ghosts[0].hits_at(3));
+        assert_eq!((ghosts[1].tail, ghosts[1].cycle, ghosts[1].hits.clone()), (1, 6, vec![3, 6]));
+    }
+
+    // 4Z is only met on the way into the loop of 4B
+    #[test]
+    fn end_only_in_the_tail() {
+        let tail = "4A = (4Z, 4Z)\n4Z = (4B, 4B)\n4B = (4B, 4B)\n";
+        let network = Network::parse(tail.lines());
+        let ghosts = ghosts(&network, "L");
+        assert_eq!((ghosts[0].tail, ghosts[0].cycle, ghosts[0].hits.clone()), (2, 1, vec![1]));
+        assert_eq!(ghosts[0].residues().count(), 0);
+        assert_eq!(walk(tail, "L").unwrap(), 1);
+        // a second ghost ending every other step, but not at step 1
+        let other = "5A = (5Z, 5Z)\n5Z = (5A, 5A)\n";
+        assert_eq!(walk(&format!("{}{}", tail, other), "L").unwrap(), 1);
+        let late = "6A = (6B, 6B)\n6B = (6Z, 6Z)\n6Z = (6B, 6B)\n";
+        assert!(matches!(walk(&format!("{}{}", tail, late), "L"), Err(WalkError::NeverTogether)));
+    }
+
+    // both ghosts go around 3 nodes, one ending at steps 1 mod 3 and the other at 2 mod 3
+    #[test]
+    fn never_together() {
+        let input = "1A = (1Z, 1Z)\n1Z = (1B, 1B)\n1B = (1A, 1A)\n2A = (2B, 2B)\n2B = (2Z, 2Z)\n2Z = (2A, 2A)\n";
+        assert!(matches!(walk(input, "L"), Err(WalkError::NeverTogether)));
+    }
+
+    #[test]
+    fn never_ends() {
+        match walk("1A = (1Z, 1Z)\n1Z = (1A, 1A)\n3A = (3A, 3A)\n", "L") {
+            Err(WalkError::NeverEnds(label)) => assert_eq!(label, "3A"),
+            _ => panic!("3A never reaches an end")
+        }
+    }
+
+    #[test]
+    fn no_start() {
+        assert!(matches!(walk("1B = (1Z, 1Z)\n1Z = (1B, 1B)\n", "L"), Err(WalkError::NoStart)));
+    }
+
+    // {1A, 1B} and {1Z} are cycles, 1C and 1D only lead into them
+    #[test]
+    fn strongly_connected_components() {
+        let network = Network::parse("1A = (1B, 1C)\n1B = (1A, 1A)\n1C = (1Z, 1Z)\n1Z = (1Z, 1Z)\n1D = (1A, 1D)\n".lines());
+        let components = network.components();
+        let id = |label| components[network.id(label).unwrap()];
+        assert_eq!(id("1A"), id("1B"));
+        let mut distinct = vec![id("1A"), id("1C"), id("1Z"), id("1D")];
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 4);
+        // in topological order : a component only leads to ones numbered after it
+        assert!(id("1D") < id("1A") && id("1A") < id("1C") && id("1C") < id("1Z"));
+        let reachable = network.reachable(network.id("1C").unwrap());
+        assert_eq!(reachable.iter().filter(|&&r| r).count(), 2);
+    }
+
+    #[test]
+    fn dot_output() {
+        let dot = Network::parse("11A = (11B, 11Z)\n11B = (11Z, 11Z)\n11Z = (11A, 11A)\n".lines()).to_dot();
+        assert!(dot.starts_with("digraph network {\n") && dot.ends_with("}\n"));
+        assert!(dot.contains("\"11A\" [style=filled, fillcolor=palegreen];"));
+        assert!(dot.contains("\"11Z\" [style=filled, fillcolor=salmon];"));
+        assert!(!dot.contains("\"11B\" [style"));
+        assert!(dot.contains("\"11A\" -> \"11B\" [label=\"L\"];"));
+        assert!(dot.contains("\"11A\" -> \"11Z\" [label=\"R\"];"));
+        // both directions to the same node make one edge
+        assert!(dot.contains("\"11B\" -> \"11Z\" [label=\"LR\"];"));
+        assert_eq!(dot.matches(" -> ").count(), 4);
+    }
+
+    #[test]
+    fn digit_labels() {
+        let network = Network::parse(GHOSTS.lines());
+        assert_eq!(network.labels.len(), 8);
+        let id = |label| network.id(label).unwrap();
+        assert_eq!(network.step(id("11A"), Direction::Left), id("11B"));
+        assert_eq!(network.step(id("11B"), Direction::Right), id("11Z"));
+        assert_eq!(network.step(id("22C"), Direction::Left), id("22Z"));
+        assert_eq!(network.step(id("XXX"), Direction::Right), id("XXX"));
+        assert_eq!(network.label(id("22B")), "22B");
+        assert_eq!(network.ends_with('A').iter().filter(|&&a| a).count(), 2);
+    }
 }
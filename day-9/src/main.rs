@@ -1,32 +1,226 @@
-use std::fs::read_to_string;
+use std::{env::args, fmt::{self, Display, Formatter}, fs::read_to_string};
 
-fn discrete_differentiation(v : &Vec<i64>) -> Vec<i64> {
-    (1..v.len()).map(|index| v[index] - v[index - 1]).collect()
+use num::{BigInt, BigRational, Zero};
+
+// None if a difference does not fit in an i64
+fn discrete_differentiation(v : &[i64]) -> Option<Vec<i64>> {
+    (1..v.len()).map(|index| v[index].checked_sub(v[index - 1])).collect()
+}
+
+#[derive(Debug)]
+enum HistoryError {
+    Empty,
+    // the differences never all reach 0 : any polynomial would need all the values to fit
+    NotPolynomial(usize),
+    // a difference or an extrapolated value does not fit in an i64
+    Overflow
 }
 
-fn next_value(v : Vec<i64>) -> Option<i64> {
-    Some(if v.iter().all(|x| x == &0) {
-        0
-    } else {
-        let n = next_value(discrete_differentiation(&v))?;
-        v.last()? + n
-    })
+impl Display for HistoryError {
+    fn fmt(&self, f : &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Empty => write!(f, "empty history"),
+            HistoryError::NotPolynomial(len) => write!(f, "the {} values never reduce to zero differences", len),
+            HistoryError::Overflow => write!(f, "overflow of i64")
+        }
+    }
 }
 
-fn previous_value(v : Vec<i64>) -> Option<i64> {
-    Some(if v.iter().all(|x| x == &0) {
-        0
-    } else {
-        let n = previous_value(discrete_differentiation(&v))?;
-        v.get(0)? - n
-    })
+// the edges of the difference table, down to the last row that is not all zeros
+struct History {
+    // first value of each row : the forward differences of the first value
+    first : Vec<i64>,
+    // last value of each row : the backward differences of the last value
+    last : Vec<i64>
+}
+
+impl History {
+    fn new(values : Vec<i64>) -> Result<Self, HistoryError> {
+        if values.is_empty() {
+            return Err(HistoryError::Empty);
+        }
+        let (len, mut first, mut last) = (values.len(), vec![], vec![]);
+        let mut row = values;
+        while row.iter().any(|x| *x != 0) {
+            match (row.first(), row.last()) {
+                (Some(&f), Some(&l)) => {first.push(f); last.push(l)},
+                _ => return Err(HistoryError::NotPolynomial(len))
+            };
+            row = discrete_differentiation(&row).ok_or(HistoryError::Overflow)?;
+        };
+        if row.is_empty() {
+            return Err(HistoryError::NotPolynomial(len));
+        }
+        Ok(Self { first, last })
+    }
+
+    // a history of zeros has degree 0, like a constant one
+    fn degree(&self) -> usize {
+        self.first.len().saturating_sub(1)
+    }
+
+    // the value `k` steps after the last one, by Newton's backward formula
+    fn forward(&self, k : u64) -> Result<i64, HistoryError> {
+        Self::newton(&self.last, k as i128, 1)
+    }
+
+    // the value `k` steps before the first one, by Newton's forward formula
+    fn backward(&self, k : u64) -> Result<i64, HistoryError> {
+        Self::newton(&self.first, k as i128, -1)
+    }
+
+    // sum of sign^j * C(k + j - 1, j) * differences[j]
+    fn newton(differences : &[i64], k : i128, sign : i128) -> Result<i64, HistoryError> {
+        let mut binomial : i128 = 1;
+        let mut sum : i128 = 0;
+        for (j, &d) in differences.iter().enumerate() {
+            let j = j as i128;
+            if j > 0 {
+                binomial = binomial.checked_mul(k + j - 1).ok_or(HistoryError::Overflow)? / j * sign;
+            }
+            sum = binomial.checked_mul(d as i128).and_then(|term| sum.checked_add(term)).ok_or(HistoryError::Overflow)?;
+        };
+        i64::try_from(sum).map_err(|_| HistoryError::Overflow)
+    }
+
+    // coefficients of the fitted polynomial, constant term first,
+    // with the first value at x = 0 : the sum of first[j] * x (x - 1) ... (x - j + 1) / j!
+    fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients = vec![BigRational::zero(); self.first.len()];
+        let (mut falling, mut factorial) = (vec![BigInt::from(1)], BigInt::from(1));
+        for (j, &d) in self.first.iter().enumerate() {
+            if j > 0 {
+                let mut next = vec![BigInt::zero(); falling.len() + 1];
+                for (i, c) in falling.iter().enumerate() {
+                    next[i + 1] += c;
+                    next[i] -= c * (j - 1);
+                };
+                falling = next;
+                factorial *= j;
+            }
+            for (i, c) in falling.iter().enumerate() {
+                coefficients[i] += BigRational::new(c * d, factorial.clone());
+            }
+        };
+        coefficients
+    }
 }
 
 fn parse_line(line : &str) -> Vec<i64> {
     line.split_ascii_whitespace().filter_map(|s| s.parse::<i64>().ok()).collect()
 }
 
+// every history must be valid for the parts to be answered
+fn total<F>(histories : &[Result<History, HistoryError>], extrapolate : F) -> Result<i64, String>
+where
+    F : Fn(&History) -> Result<i64, HistoryError>
+{
+    let invalid = histories.iter().filter(|h| h.is_err()).count();
+    if invalid > 0 {
+        return Err(format!("{} of the {} histories are invalid", invalid, histories.len()));
+    }
+    histories.iter().flatten().try_fold(0_i64, |sum, h| extrapolate(h)?.checked_add(sum).ok_or(HistoryError::Overflow))
+        .map_err(|e| e.to_string())
+}
+
+// usage : day-9 [--fit]
 fn main() {
-    println!("part 1 : {}", read_to_string("input.txt").unwrap_or("".to_string()).lines().map(parse_line).filter_map(next_value).sum::<i64>());
-    println!("part 2 : {}", read_to_string("input.txt").unwrap_or("".to_string()).lines().map(parse_line).filter_map(previous_value).sum::<i64>());
+    let histories = read_to_string("input.txt").unwrap_or("".to_string()).lines().map(parse_line).map(History::new).collect::<Vec<_>>();
+    histories.iter().enumerate().for_each(|(i, h)| if let Err(e) = h {
+        eprintln!("line {} : {}", i + 1, e)
+    });
+    if args().any(|arg| arg == "--fit") {
+        for history in histories.iter().flatten() {
+            let coefficients = history.coefficients();
+            println!("degree {} : {}", history.degree(), coefficients.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" "));
+        }
+    }
+    match total(&histories, |h| h.forward(1)) {
+        Ok(sum) => println!("part 1 : {}", sum),
+        Err(e) => eprintln!("part 1 : {}", e)
+    };
+    match total(&histories, |h| h.backward(1)) {
+        Ok(sum) => println!("part 2 : {}", sum),
+        Err(e) => eprintln!("part 2 : {}", e)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(f : impl Fn(i64) -> i64, len : i64) -> History {
+        History::new((0..len).map(f).collect()).unwrap()
+    }
+
+    fn rational(n : i64, d : i64) -> BigRational {
+        BigRational::new(BigInt::from(n), BigInt::from(d))
+    }
+
+    #[test]
+    fn extrapolates_a_square_k_steps() {
+        let square = |x : i64| x * x + 1;
+        let h = history(square, 5);
+        assert_eq!(h.degree(), 2);
+        for k in 0..20 {
+            assert_eq!(h.forward(k).unwrap(), square(4 + k as i64), "forward {}", k);
+            assert_eq!(h.backward(k).unwrap(), square(-(k as i64)), "backward {}", k);
+        }
+    }
+
+    #[test]
+    fn extrapolates_a_cubic_k_steps() {
+        let cubic = |x : i64| 2 * x * x * x - 7 * x + 3;
+        let h = history(cubic, 6);
+        assert_eq!(h.degree(), 3);
+        for k in [1, 2, 5, 100] {
+            assert_eq!(h.forward(k).unwrap(), cubic(5 + k as i64));
+            assert_eq!(h.backward(k).unwrap(), cubic(-(k as i64)));
+        }
+    }
+
+    #[test]
+    fn example() {
+        let histories = ["0 3 6 9 12 15", "1 3 6 10 15 21", "10 13 16 21 30 45"].map(|l| History::new(parse_line(l)).unwrap());
+        assert_eq!(histories.iter().map(|h| h.forward(1).unwrap()).sum::<i64>(), 114);
+        assert_eq!(histories.iter().map(|h| h.backward(1).unwrap()).sum::<i64>(), 2);
+    }
+
+    #[test]
+    fn not_polynomial() {
+        assert!(matches!(History::new(vec![1, 2, 4, 8]), Err(HistoryError::NotPolynomial(4))));
+        assert!(matches!(History::new(vec![5]), Err(HistoryError::NotPolynomial(1))));
+        assert!(matches!(History::new(vec![]), Err(HistoryError::Empty)));
+        // a history of zeros is fine, even a single one
+        assert!(History::new(vec![0]).is_ok());
+    }
+
+    #[test]
+    fn overflow() {
+        assert!(matches!(History::new(vec![i64::MIN, i64::MAX, 0]), Err(HistoryError::Overflow)));
+        let h = History::new(vec![i64::MAX - 2, i64::MAX - 1, i64::MAX]).unwrap();
+        assert!(matches!(h.forward(1), Err(HistoryError::Overflow)));
+        assert_eq!(h.backward(1).unwrap(), i64::MAX - 3);
+        let h = History::new(vec![i64::MIN + 2, i64::MIN + 1, i64::MIN]).unwrap();
+        assert!(matches!(h.forward(1), Err(HistoryError::Overflow)));
+    }
+
+    #[test]
+    fn invalid_histories_fail_the_total() {
+        let histories = ["1 2 3", "1 2 4 8", "0 0"].map(|l| History::new(parse_line(l)));
+        assert!(total(&histories, |h| h.forward(1)).is_err());
+        let histories = ["1 2 3", "0 0"].map(|l| History::new(parse_line(l)));
+        assert_eq!(total(&histories, |h| h.forward(1)), Ok(4));
+        let histories = [vec![i64::MAX, i64::MAX], vec![1, 1]].map(History::new);
+        assert!(total(&histories, |h| h.forward(1)).is_err());
+    }
+
+    #[test]
+    fn coefficients_of_known_polynomials() {
+        assert_eq!(history(|x| x * x + 1, 5).coefficients(), vec![rational(1, 1), rational(0, 1), rational(1, 1)]);
+        // triangular numbers : x (x + 1) / 2
+        assert_eq!(history(|x| x * (x + 1) / 2, 5).coefficients(), vec![rational(0, 1), rational(1, 2), rational(1, 2)]);
+        assert_eq!(history(|_| 7, 3).coefficients(), vec![rational(7, 1)]);
+        assert_eq!(history(|_| 0, 3).coefficients(), vec![]);
+    }
 }
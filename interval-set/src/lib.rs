@@ -0,0 +1,250 @@
+use std::slice::Iter;
+
+// half-open range of integers : `start` is included, `end` is not
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Range {
+    pub start : u64,
+    pub end : u64
+}
+
+impl Range {
+    pub fn new(start : u64, end : u64) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    pub fn contains(&self, n : u64) -> bool {
+        self.start <= n && n < self.end
+    }
+
+    pub fn intersection(&self, other : &Range) -> Option<Range> {
+        let r = Range::new(self.start.max(other.start), self.end.min(other.end));
+        (!r.is_empty()).then_some(r)
+    }
+}
+
+// sorted list of disjoint, non-touching, non-empty ranges
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges : Vec<Range>
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self { ranges: vec![] }
+    }
+
+    // merges `r` with every range it overlaps or touches
+    pub fn insert(&mut self, r : Range) {
+        if r.is_empty() {
+            return;
+        }
+        let first = self.ranges.partition_point(|s| s.end < r.start);
+        let last = self.ranges.partition_point(|s| s.start <= r.end);
+        let merged = self.ranges[first..last].iter().fold(r, |acc, s| Range::new(acc.start.min(s.start), acc.end.max(s.end)));
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn contains(&self, n : u64) -> bool {
+        match self.ranges.partition_point(|s| s.end <= n) {
+            i if i < self.ranges.len() => self.ranges[i].contains(n),
+            _ => false
+        }
+    }
+
+    // total number of integers in the set
+    pub fn len(&self) -> u64 {
+        self.ranges.iter().map(Range::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn iter(&self) -> Iter<'_, Range> {
+        self.ranges.iter()
+    }
+
+    pub fn union(&self, other : &IntervalSet) -> IntervalSet {
+        self.iter().chain(other.iter()).copied().collect()
+    }
+
+    pub fn intersection(&self, other : &IntervalSet) -> IntervalSet {
+        let mut res = IntervalSet::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (self.ranges[i], other.ranges[j]);
+            if let Some(r) = a.intersection(&b) {
+                res.ranges.push(r);
+            }
+            if a.end < b.end {i += 1} else {j += 1}
+        };
+        res
+    }
+
+    // everything in `bound` that is not in the set
+    pub fn complement(&self, bound : Range) -> IntervalSet {
+        let mut res = IntervalSet::new();
+        let mut start = bound.start;
+        for r in self.iter().skip_while(|r| r.end <= bound.start).take_while(|r| r.start < bound.end) {
+            if start < r.start {
+                res.ranges.push(Range::new(start, r.start));
+            }
+            start = start.max(r.end);
+        };
+        if start < bound.end {
+            res.ranges.push(Range::new(start, bound.end));
+        }
+        res
+    }
+
+    pub fn difference(&self, other : &IntervalSet) -> IntervalSet {
+        match (self.ranges.first(), self.ranges.last()) {
+            (Some(first), Some(last)) => self.intersection(&other.complement(Range::new(first.start, last.end))),
+            _ => IntervalSet::new()
+        }
+    }
+}
+
+impl FromIterator<Range> for IntervalSet {
+    fn from_iter<I : IntoIterator<Item = Range>>(iter : I) -> Self {
+        let mut set = IntervalSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Range> for IntervalSet {
+    fn extend<I : IntoIterator<Item = Range>>(&mut self, iter : I) {
+        iter.into_iter().for_each(|r| self.insert(r))
+    }
+}
+
+impl IntoIterator for IntervalSet {
+    type Item = Range;
+    type IntoIter = std::vec::IntoIter<Range>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a IntervalSet {
+    type Item = &'a Range;
+    type IntoIter = Iter<'a, Range>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    const BOUND : u64 = 64;
+
+    // the naive model : every integer of the set
+    fn model(ranges : &[Range]) -> BTreeSet<u64> {
+        ranges.iter().flat_map(|r| r.start..r.end).collect()
+    }
+
+    fn flatten(set : &IntervalSet) -> BTreeSet<u64> {
+        set.iter().flat_map(|r| r.start..r.end).collect()
+    }
+
+    // sorted, disjoint, non-touching and non-empty
+    fn is_canonical(set : &IntervalSet) -> bool {
+        set.iter().all(|r| !r.is_empty()) && set.ranges.windows(2).all(|w| w[0].end < w[1].start)
+    }
+
+    fn range() -> impl Strategy<Value = Range> {
+        (0..BOUND, 0..16_u64).prop_map(|(start, len)| Range::new(start, start + len))
+    }
+
+    fn ranges() -> impl Strategy<Value = Vec<Range>> {
+        prop::collection::vec(range(), 0..8)
+    }
+
+    proptest! {
+        #[test]
+        fn insert_coalesces(rs in ranges()) {
+            let set = rs.iter().copied().collect::<IntervalSet>();
+            prop_assert!(is_canonical(&set));
+            prop_assert_eq!(flatten(&set), model(&rs));
+        }
+
+        #[test]
+        fn contains_and_len(rs in ranges()) {
+            let set = rs.iter().copied().collect::<IntervalSet>();
+            let m = model(&rs);
+            prop_assert_eq!(set.len(), m.len() as u64);
+            prop_assert_eq!(set.is_empty(), m.is_empty());
+            for n in 0..BOUND + 16 {
+                prop_assert_eq!(set.contains(n), m.contains(&n));
+            }
+        }
+
+        #[test]
+        fn union(a in ranges(), b in ranges()) {
+            let u = a.iter().copied().collect::<IntervalSet>().union(&b.iter().copied().collect());
+            prop_assert!(is_canonical(&u));
+            prop_assert_eq!(flatten(&u), model(&a).union(&model(&b)).copied().collect());
+        }
+
+        #[test]
+        fn intersection(a in ranges(), b in ranges()) {
+            let i = a.iter().copied().collect::<IntervalSet>().intersection(&b.iter().copied().collect());
+            prop_assert!(is_canonical(&i));
+            prop_assert_eq!(flatten(&i), model(&a).intersection(&model(&b)).copied().collect());
+        }
+
+        #[test]
+        fn difference(a in ranges(), b in ranges()) {
+            let d = a.iter().copied().collect::<IntervalSet>().difference(&b.iter().copied().collect());
+            prop_assert!(is_canonical(&d));
+            prop_assert_eq!(flatten(&d), model(&a).difference(&model(&b)).copied().collect());
+        }
+
+        #[test]
+        fn complement(rs in ranges(), bound in range()) {
+            let c = rs.iter().copied().collect::<IntervalSet>().complement(bound);
+            prop_assert!(is_canonical(&c));
+            prop_assert_eq!(flatten(&c), (bound.start..bound.end).filter(|n| !model(&rs).contains(n)).collect());
+        }
+
+        #[test]
+        fn range_intersection(a in range(), b in range()) {
+            let expected = model(&[a]).intersection(&model(&[b])).copied().collect::<BTreeSet<_>>();
+            prop_assert_eq!(a.intersection(&b).map_or(BTreeSet::new(), |r| model(&[r])), expected);
+        }
+    }
+
+    #[test]
+    fn insert_bridging_several_intervals() {
+        let mut set = [Range::new(0, 2), Range::new(4, 6), Range::new(8, 10), Range::new(20, 25)].into_iter().collect::<IntervalSet>();
+        set.insert(Range::new(1, 9));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![Range::new(0, 10), Range::new(20, 25)]);
+    }
+
+    #[test]
+    fn insert_touching_merges() {
+        let mut set = [Range::new(0, 2), Range::new(4, 6)].into_iter().collect::<IntervalSet>();
+        set.insert(Range::new(2, 4));
+        assert_eq!(set.iter().copied().collect::<Vec<_>>(), vec![Range::new(0, 6)]);
+    }
+
+    #[test]
+    fn empty_ranges_are_ignored() {
+        let set = [Range::new(3, 3), Range::new(5, 2)].into_iter().collect::<IntervalSet>();
+        assert!(set.is_empty());
+        assert_eq!(set.len(), 0);
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::{HashMap, VecDeque}, hash::Hash, iter::Peekable};
+
+// run-length encoding : consecutive equal items become (count, item)
+pub struct GroupCounter<I : Iterator>
+where
+    I::Item : PartialEq
+{
+    iter : Peekable<I>
+}
+
+impl<I : Iterator> Iterator for GroupCounter<I>
+where
+    I::Item : PartialEq
+{
+    type Item = (usize, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.iter.next()?;
+        let mut count : usize = 1;
+        while self.iter.next_if_eq(&x).is_some() {
+            count += 1;
+        }
+        Some((count, x))
+    }
+}
+
+// run-length decoding : (count, item) becomes count copies of item
+pub struct Decoder<I, T> {
+    iter : I,
+    current : Option<(usize, T)>
+}
+
+impl<I, T> Iterator for Decoder<I, T>
+where
+    I : Iterator<Item = (usize, T)>,
+    T : Clone
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.current.take() {
+                Some((0, _)) | None => self.current = Some(self.iter.next()?),
+                Some((1, x)) => return Some(x),
+                Some((n, x)) => {
+                    self.current = Some((n - 1, x.clone()));
+                    return Some(x)
+                }
+            }
+        }
+    }
+}
+
+// the items between separators, like `str::split` : empty pieces are kept
+pub struct SplitOn<I, F> {
+    iter : I,
+    is_separator : F,
+    done : bool
+}
+
+impl<I, F> Iterator for SplitOn<I, F>
+where
+    I : Iterator,
+    F : FnMut(&I::Item) -> bool
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut piece = vec![];
+        for x in self.iter.by_ref() {
+            if (self.is_separator)(&x) {
+                return Some(piece);
+            }
+            piece.push(x);
+        }
+        self.done = true;
+        Some(piece)
+    }
+}
+
+// every run of `size` consecutive items, like `slice::windows`
+pub struct Windows<I : Iterator> {
+    iter : I,
+    size : usize,
+    window : VecDeque<I::Item>
+}
+
+impl<I : Iterator> Iterator for Windows<I>
+where
+    I::Item : Clone
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.window.len() == self.size {
+            self.window.pop_front();
+        }
+        while self.window.len() < self.size {
+            self.window.push_back(self.iter.next()?);
+        }
+        Some(self.window.iter().cloned().collect())
+    }
+}
+
+pub trait IterExt : Iterator + Sized {
+    fn counts(self) -> GroupCounter<Self>
+    where
+        Self::Item : PartialEq
+    {
+        GroupCounter { iter: self.peekable() }
+    }
+
+    fn decode<T>(self) -> Decoder<Self, T>
+    where
+        Self : Iterator<Item = (usize, T)>,
+        T : Clone
+    {
+        Decoder { iter: self, current: None }
+    }
+
+    fn split_on<F>(self, is_separator : F) -> SplitOn<Self, F>
+    where
+        F : FnMut(&Self::Item) -> bool
+    {
+        SplitOn { iter: self, is_separator, done: false }
+    }
+
+    // panics if `size` is 0
+    fn windows(self, size : usize) -> Windows<Self>
+    where
+        Self::Item : Clone
+    {
+        assert!(size > 0, "windows of size 0");
+        Windows { iter: self, size, window: VecDeque::with_capacity(size) }
+    }
+
+    // how many times each item appears
+    fn histogram(self) -> HashMap<Self::Item, usize>
+    where
+        Self::Item : Hash + Eq
+    {
+        self.fold(HashMap::new(), |mut map, x| {
+            *map.entry(x).or_insert(0) += 1;
+            map
+        })
+    }
+}
+
+impl<I : Iterator> IterExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_groups_consecutive_items() {
+        assert_eq!("aaabccaa".chars().counts().collect::<Vec<_>>(), vec![(3, 'a'), (1, 'b'), (2, 'c'), (2, 'a')]);
+        assert_eq!("".chars().counts().count(), 0);
+    }
+
+    #[test]
+    fn decode_skips_zero_counts() {
+        assert_eq!([(0, 'a'), (2, 'b'), (0, 'c'), (1, 'd'), (0, 'e')].into_iter().decode().collect::<String>(), "bbd");
+        assert_eq!([(0, 'a')].into_iter().decode().count(), 0);
+    }
+
+    #[test]
+    fn counts_then_decode_round_trips() {
+        for s in ["", "a", "aaabccaa", "abcabc", "zzzzzz"] {
+            assert_eq!(s.chars().counts().decode().collect::<String>(), s);
+        }
+    }
+
+    #[test]
+    fn split_on_keeps_empty_pieces() {
+        let split = |s : &str| s.chars().split_on(|c| *c == ',').map(|v| v.into_iter().collect::<String>()).collect::<Vec<_>>();
+        for s in ["a,b", ",a,b", "a,b,", "a,,b", ",,", "", "abc"] {
+            assert_eq!(split(s), s.split(',').collect::<Vec<_>>(), "splitting {:?}", s);
+        }
+    }
+
+    #[test]
+    fn windows_of_every_size() {
+        assert_eq!((1..=4).windows(2).collect::<Vec<_>>(), vec![vec![1, 2], vec![2, 3], vec![3, 4]]);
+        assert_eq!((1..=4).windows(4).collect::<Vec<_>>(), vec![vec![1, 2, 3, 4]]);
+        assert_eq!((1..=4).windows(5).count(), 0);
+        assert_eq!((1..1).windows(1).count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn windows_of_size_zero_panic() {
+        (1..=4).windows(0);
+    }
+
+    #[test]
+    fn histogram_counts_every_item() {
+        let h = "abracadabra".chars().histogram();
+        assert_eq!(h.len(), 5);
+        assert_eq!((h[&'a'], h[&'b'], h[&'r'], h[&'c'], h[&'d']), (5, 2, 2, 1, 1));
+        assert!("".chars().histogram().is_empty());
+    }
+}